@@ -1,37 +1,239 @@
-use advent_of_code_2022::{print_2d_array, read_file_lines_as};
+use advent_of_code_2022::{print_2d_array, read_file_to_string};
 use array2d::Array2D;
-use std::str::FromStr;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Register {
+    X,
+    Y,
+    Z,
+}
+
+const NUM_OF_REGISTERS: usize = 3;
+
+impl FromStr for Register {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" => Ok(Self::X),
+            "y" => Ok(Self::Y),
+            "z" => Ok(Self::Z),
+            _ => Err(format!("Invalid register '{s}'")),
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::X => write!(f, "x"),
+            Self::Y => write!(f, "y"),
+            Self::Z => write!(f, "z"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Instruction {
     Noop,
-    AddX(i32),
+    Add(Register, i32),
+    Sub(Register, i32),
+    // jumps are relative to the jump instruction itself
+    Jump(isize),
+    JumpIfNotZero(Register, isize),
+    Halt,
+}
+
+fn parse_operand<T: FromStr>(s: &str, operand: Option<&str>) -> Result<T, String> {
+    operand
+        .ok_or(format!("Missing operand in '{s}'"))?
+        .parse()
+        .map_err(|_| format!("Invalid operand in '{s}'"))
 }
 
 impl FromStr for Instruction {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "noop" {
-            Ok(Self::Noop)
-        } else if let Some((cmd, x)) = s.split_once(' ') {
-            if cmd == "addx" {
-                let x = i32::from_str(x).map_err(|_| "Invalid format".to_string())?;
-                Ok(Self::AddX(x))
-            } else {
-                Err("Invalid format".to_string())
+        let mut parts = s.split_whitespace();
+        let mnemonic = parts.next().ok_or("Empty instruction".to_string())?;
+        let instruction = match mnemonic {
+            "noop" => Self::Noop,
+            "halt" => Self::Halt,
+            "jmp" => Self::Jump(parse_operand(s, parts.next())?),
+            "jnz" => Self::JumpIfNotZero(
+                parse_operand(s, parts.next())?,
+                parse_operand(s, parts.next())?,
+            ),
+            _ => {
+                if let Some(register) = mnemonic.strip_prefix("add") {
+                    Self::Add(
+                        Register::from_str(register)?,
+                        parse_operand(s, parts.next())?,
+                    )
+                } else if let Some(register) = mnemonic.strip_prefix("sub") {
+                    Self::Sub(
+                        Register::from_str(register)?,
+                        parse_operand(s, parts.next())?,
+                    )
+                } else {
+                    return Err(format!("Unknown instruction '{s}'"));
+                }
             }
-        } else {
-            Err("Invalid format".to_string())
+        };
+        if parts.next().is_some() {
+            return Err(format!("Too many operands in '{s}'"));
+        }
+        Ok(instruction)
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Noop => write!(f, "noop"),
+            Self::Add(register, value) => write!(f, "add{register} {value}"),
+            Self::Sub(register, value) => write!(f, "sub{register} {value}"),
+            Self::Jump(offset) => write!(f, "jmp {offset}"),
+            Self::JumpIfNotZero(register, offset) => write!(f, "jnz {register} {offset}"),
+            Self::Halt => write!(f, "halt"),
         }
     }
 }
 
 impl Instruction {
-    fn cycles(&self) -> i32 {
+    fn cycles(&self) -> usize {
         match self {
-            Self::Noop => 1,
-            Self::AddX(_) => 2,
+            Self::Add(..) | Self::Sub(..) => 2,
+            Self::Noop | Self::Jump(_) | Self::JumpIfNotZero(..) | Self::Halt => 1,
+        }
+    }
+}
+
+// what the CPU looks like during a cycle, before the current instruction takes effect
+struct CycleState<'a> {
+    cycle: usize,
+    registers: &'a [i32; NUM_OF_REGISTERS],
+    program_counter: usize,
+    instruction: &'a Instruction,
+    // how many cycles of the current instruction have already passed
+    instruction_cycle: usize,
+}
+
+impl CycleState<'_> {
+    fn get(&self, register: Register) -> i32 {
+        self.registers[register as usize]
+    }
+}
+
+trait CycleObserver {
+    fn observe(&mut self, state: &CycleState);
+}
+
+const MAX_CYCLES: usize = 1_000_000;
+
+struct Cpu {
+    registers: [i32; NUM_OF_REGISTERS],
+    program_counter: usize,
+    elapsed_cycles: usize,
+}
+
+impl Cpu {
+    fn new() -> Self {
+        let mut registers = [0; NUM_OF_REGISTERS];
+        registers[Register::X as usize] = 1;
+        Self {
+            registers,
+            program_counter: 0,
+            elapsed_cycles: 0,
+        }
+    }
+
+    /*
+     * runs the program until it halts or jumps outside of itself, notifying the observers during
+     * every cycle; instructions only take effect at the end of their last cycle
+     */
+    fn run(
+        &mut self,
+        program: &[Instruction],
+        observers: &mut [&mut dyn CycleObserver],
+    ) -> Result<(), String> {
+        while let Some(instruction) = program.get(self.program_counter) {
+            for instruction_cycle in 0..instruction.cycles() {
+                self.elapsed_cycles += 1;
+                if self.elapsed_cycles > MAX_CYCLES {
+                    return Err(format!("The program did not halt in {MAX_CYCLES} cycles"));
+                }
+                let state = CycleState {
+                    cycle: self.elapsed_cycles,
+                    registers: &self.registers,
+                    program_counter: self.program_counter,
+                    instruction,
+                    instruction_cycle,
+                };
+                for observer in observers.iter_mut() {
+                    observer.observe(&state);
+                }
+            }
+
+            if !self.execute(instruction)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // returns whether execution should continue, or an error if a register overflows
+    fn execute(&mut self, instruction: &Instruction) -> Result<bool, String> {
+        let mut offset = 1;
+        match *instruction {
+            Instruction::Noop => {}
+            Instruction::Add(register, value) | Instruction::Sub(register, value) => {
+                let current = self.registers[register as usize];
+                let result = if matches!(instruction, Instruction::Add(..)) {
+                    current.checked_add(value)
+                } else {
+                    current.checked_sub(value)
+                };
+                self.registers[register as usize] = result.ok_or(format!(
+                    "Register {register} overflowed in cycle {} executing '{instruction}' at instruction {}",
+                    self.elapsed_cycles, self.program_counter
+                ))?;
+            }
+            Instruction::Jump(jump_offset) => offset = jump_offset,
+            Instruction::JumpIfNotZero(register, jump_offset) => {
+                if self.registers[register as usize] != 0 {
+                    offset = jump_offset;
+                }
+            }
+            Instruction::Halt => return Ok(false),
+        }
+
+        match self.program_counter.checked_add_signed(offset) {
+            Some(program_counter) => {
+                self.program_counter = program_counter;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+// wide enough to never overflow, even with extreme values of X over `MAX_CYCLES` cycles
+struct SignalStrengthMeter {
+    sum_of_interesting_signal_strengths: i128,
+}
+
+impl CycleObserver for SignalStrengthMeter {
+    fn observe(&mut self, state: &CycleState) {
+        if state.cycle % 40 == 20 {
+            self.sum_of_interesting_signal_strengths +=
+                state.get(Register::X) as i128 * state.cycle as i128;
         }
     }
 }
@@ -40,84 +242,459 @@ const CRT_WIDTH: usize = 40;
 const CRT_HEIGHT: usize = 6;
 
 struct Crt {
-    x: i32,
-    elapsed_ticks: usize,
     display: Array2D<char>,
 }
 
 impl Crt {
     fn new() -> Self {
         Self {
-            x: 1,
-            elapsed_ticks: 0,
             display: Array2D::filled_with('.', CRT_HEIGHT, CRT_WIDTH),
         }
     }
+}
 
-    fn run_program(&mut self, instructions: &[Instruction]) -> i32 {
-        let mut sum_of_interesting_signal_strengths = 0;
+impl CycleObserver for Crt {
+    fn observe(&mut self, state: &CycleState) {
+        let row = (state.cycle - 1) / CRT_WIDTH;
+        let column = (state.cycle - 1) % CRT_WIDTH;
+        let x = state.get(Register::X) as i64;
+        if (x - 1..=x + 1).contains(&(column as i64)) {
+            // cycles after the last row are not drawn
+            let _ = self.display.set(row, column, '#');
+        }
+    }
+}
 
-        let mut instruction_iter = instructions.iter();
-        let Some(mut current_instruction) = instruction_iter.next() else {
-            return sum_of_interesting_signal_strengths;
-        };
-        let mut remaining_ticks = current_instruction.cycles();
+// glyphs of the font used by the puzzles, each 4 pixels wide and 6 pixels tall
+const STANDARD_GLYPHS: [(char, &[&str]); 17] = [
+    ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', &[".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+// a fixed-width bitmap font, where `#` is a lit pixel and anything else is dark
+struct Font {
+    glyph_width: usize,
+    glyph_height: usize,
+    // number of empty columns between glyphs
+    spacing: usize,
+    glyphs: HashMap<Vec<String>, char>,
+}
 
-        loop {
-            self.elapsed_ticks += 1;
+impl Font {
+    fn new(
+        glyph_width: usize,
+        glyph_height: usize,
+        spacing: usize,
+        glyphs: &[(char, &[&str])],
+    ) -> Self {
+        let glyphs = glyphs
+            .iter()
+            .map(|(c, rows)| (rows.iter().map(|r| r.to_string()).collect(), *c))
+            .collect();
+        Self {
+            glyph_width,
+            glyph_height,
+            spacing,
+            glyphs,
+        }
+    }
 
-            let row = (self.elapsed_ticks - 1) / CRT_WIDTH;
-            let column = (self.elapsed_ticks - 1) % CRT_WIDTH;
-            if self.x == column as i32 || self.x - 1 == column as i32 || self.x + 1 == column as i32
-            {
-                self.display.set(row, column, '#').unwrap();
-            }
+    fn standard() -> Self {
+        Self::new(4, 6, 1, &STANDARD_GLYPHS)
+    }
 
-            if self.should_check_signal_strength() {
-                let signal_strength = self.calculate_signal_strength_at_current_state();
-                sum_of_interesting_signal_strengths += signal_strength;
-            }
+    fn recognise(&self, display: &Array2D<char>) -> Result<String, String> {
+        if display.num_rows() != self.glyph_height {
+            return Err(format!(
+                "The display is {} pixels tall, but glyphs are {} pixels tall",
+                display.num_rows(),
+                self.glyph_height
+            ));
+        }
 
-            if remaining_ticks == 0 {
-                let Some(next_instruction) = instruction_iter.next() else {
-                    return sum_of_interesting_signal_strengths;
-                };
-                current_instruction = next_instruction;
-                remaining_ticks = current_instruction.cycles();
+        let glyph_stride = self.glyph_width + self.spacing;
+        let num_of_glyphs = (display.num_columns() + self.spacing) / glyph_stride;
+        let mut text = String::new();
+        let mut unrecognised = Vec::new();
+        for i in 0..num_of_glyphs {
+            let offset = i * glyph_stride;
+            let glyph = (0..self.glyph_height)
+                .map(|row| {
+                    (offset..offset + self.glyph_width)
+                        .map(|column| match display.get(row, column) {
+                            Some('#') => '#',
+                            _ => '.',
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>();
+            match self.glyphs.get(&glyph) {
+                Some(&c) => text.push(c),
+                None => unrecognised.push(format!(
+                    "glyph {} at column {offset}:\n{}",
+                    i + 1,
+                    glyph.join("\n")
+                )),
             }
+        }
+
+        if unrecognised.is_empty() {
+            Ok(text)
+        } else {
+            Err(format!(
+                "Could not recognise {} glyphs, the others read '{text}':\n{}",
+                unrecognised.len(),
+                unrecognised.join("\n")
+            ))
+        }
+    }
+}
+
+struct Tracer {
+    lines: Vec<String>,
+}
+
+impl CycleObserver for Tracer {
+    fn observe(&mut self, state: &CycleState) {
+        let registers = [Register::X, Register::Y, Register::Z]
+            .map(|register| format!("{register}={}", state.get(register)))
+            .join(" ");
+        self.lines.push(format!(
+            "cycle {:4}: {registers} | {:4}: {}",
+            state.cycle, state.program_counter, state.instruction
+        ));
+    }
+}
+
+// comments start with `;` and last until the end of the line
+fn strip_comment(line: &str) -> &str {
+    match line.split_once(';') {
+        Some((code, _)) => code,
+        None => line,
+    }
+    .trim()
+}
+
+fn check_symbol_name(name: &str, symbols: &HashMap<&str, Symbol>) -> Result<(), String> {
+    let mut chars = name.chars();
+    let is_identifier = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_identifier {
+        Err(format!("Invalid name '{name}'"))
+    } else if Register::from_str(name).is_ok() {
+        Err(format!("'{name}' is the name of a register"))
+    } else if symbols.contains_key(name) {
+        Err(format!("'{name}' is already defined"))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Symbol {
+    // index of the instruction the label points at
+    Label(usize),
+    Constant(i32),
+}
 
-            match current_instruction {
-                Instruction::Noop => {}
-                Instruction::AddX(x) => {
-                    if remaining_ticks == 1 {
-                        self.x += x;
+/*
+ * assembles a program that can use the following on top of plain instructions:
+ * - comments, starting with `;`
+ * - labels, written as `name:` either on their own line or before an instruction, pointing at the
+ *   next instruction, which can be used as the target of jumps
+ * - constants, written as `.const name value`, which can be used in place of any number
+ * errors mention the line they were found on
+ */
+fn assemble(source: &str) -> Result<Vec<Instruction>, String> {
+    let mut symbols = HashMap::new();
+    let mut lines = Vec::new();
+    for (idx, line) in source.lines().enumerate() {
+        let line_no = idx + 1;
+        let mut code = strip_comment(line);
+        if let Some(definition) = code.strip_prefix(".const ") {
+            let (name, value) = definition
+                .trim()
+                .split_once(char::is_whitespace)
+                .ok_or(format!("line {line_no}: Missing value in '{code}'"))?;
+            check_symbol_name(name, &symbols).map_err(|e| format!("line {line_no}: {e}"))?;
+            let value = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("line {line_no}: Invalid value in '{code}'"))?;
+            symbols.insert(name, Symbol::Constant(value));
+            continue;
+        }
+        if let Some((label, rest)) = code.split_once(':') {
+            let label = label.trim();
+            check_symbol_name(label, &symbols).map_err(|e| format!("line {line_no}: {e}"))?;
+            symbols.insert(label, Symbol::Label(lines.len()));
+            code = rest.trim();
+        }
+        if !code.is_empty() {
+            lines.push((line_no, code));
+        }
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, &(line_no, code))| {
+            let mut parts = code.split_whitespace();
+            let mnemonic = parts.next().unwrap();
+            let is_jump = matches!(mnemonic, "jmp" | "jnz");
+            let operands = parts
+                .map(|operand| match symbols.get(operand) {
+                    Some(Symbol::Constant(value)) => Ok(value.to_string()),
+                    Some(Symbol::Label(target)) if is_jump => {
+                        Ok((*target as isize - idx as isize).to_string())
                     }
-                }
-            }
+                    Some(Symbol::Label(_)) => Err(format!(
+                        "line {line_no}: Label '{operand}' can only be used by jumps"
+                    )),
+                    None => Ok(operand.to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Instruction::from_str(&format!("{mnemonic} {}", operands.join(" ")))
+                .map_err(|e| format!("line {line_no}: {e}"))
+        })
+        .collect()
+}
 
-            remaining_ticks -= 1;
+// remembers the first cycle of every execution of each instruction, along with the value of X
+struct ExecutionRecorder {
+    executions: Vec<Vec<(usize, i32)>>,
+}
+
+impl ExecutionRecorder {
+    fn new(program: &[Instruction]) -> Self {
+        Self {
+            executions: vec![Vec::new(); program.len()],
+        }
+    }
+}
+
+impl CycleObserver for ExecutionRecorder {
+    fn observe(&mut self, state: &CycleState) {
+        if state.instruction_cycle == 0 {
+            self.executions[state.program_counter].push((state.cycle, state.get(Register::X)));
+        }
+    }
+}
+
+const MAX_EXECUTIONS_PER_LINE: usize = 4;
+
+/*
+ * prints the program with the cycles each instruction ran in and the value of X during them,
+ * written as comments, so the output can be assembled again
+ */
+fn disassemble(program: &[Instruction]) -> Result<String, String> {
+    let mut recorder = ExecutionRecorder::new(program);
+    Cpu::new().run(program, &mut [&mut recorder])?;
+
+    let mut output = String::new();
+    for (instruction, executions) in program.iter().zip(&recorder.executions) {
+        let mut details = executions
+            .iter()
+            .take(MAX_EXECUTIONS_PER_LINE)
+            .map(|&(cycle, x)| match instruction.cycles() {
+                1 => format!("{cycle} (X={x})"),
+                n => format!("{cycle}-{} (X={x})", cycle + n - 1),
+            })
+            .collect::<Vec<_>>();
+        if executions.len() > MAX_EXECUTIONS_PER_LINE {
+            details.push(format!(
+                "and {} more",
+                executions.len() - MAX_EXECUTIONS_PER_LINE
+            ));
         }
+        let comment = if details.is_empty() {
+            "never executed".to_string()
+        } else if executions.len() == 1 && instruction.cycles() == 1 {
+            format!("cycle {}", details[0])
+        } else {
+            format!("cycles {}", details.join(", "))
+        };
+        output.push_str(&format!("{:<12}; {comment}\n", instruction.to_string()));
+    }
+    Ok(output)
+}
+
+// values of X worth considering when drawing, which are enough to light or darken any pixel
+const MIN_SPRITE_POSITION: i32 = -2;
+const MAX_SPRITE_POSITION: i32 = CRT_WIDTH as i32 + 1;
+
+/*
+ * finds the shortest program of `noop`s and `addx`s that makes the CRT draw the image, where `#`
+ * is a lit pixel and anything else is dark; as X can only change after an `addx`, whose two
+ * cycles must both see the old value, some images cannot be drawn at all, which is reported with
+ * the first pixel that cannot be drawn
+ */
+fn generate_program_drawing(image: &Array2D<char>) -> Result<Vec<Instruction>, String> {
+    if image.num_rows() != CRT_HEIGHT || image.num_columns() != CRT_WIDTH {
+        return Err(format!(
+            "The image is {}x{}, but the CRT is {CRT_WIDTH}x{CRT_HEIGHT}",
+            image.num_columns(),
+            image.num_rows()
+        ));
     }
 
-    fn should_check_signal_strength(&self) -> bool {
-        self.elapsed_ticks % 40 == 20
+    let num_of_cycles = CRT_WIDTH * CRT_HEIGHT;
+    let num_of_positions = (MAX_SPRITE_POSITION - MIN_SPRITE_POSITION + 1) as usize;
+    let to_position = |idx: usize| idx as i32 + MIN_SPRITE_POSITION;
+    let can_draw = |cycle: usize, idx: usize| {
+        let column = (cycle % CRT_WIDTH) as i32;
+        let is_lit = image.get(cycle / CRT_WIDTH, cycle % CRT_WIDTH) == Some(&'#');
+        ((to_position(idx) - column).abs() <= 1) == is_lit
+    };
+
+    // for each cycle and value of X at its start: the fewest instructions needed to get there,
+    // the value of X before the last one, and the last instruction
+    let mut best: Vec<Vec<Option<(usize, usize, Instruction)>>> =
+        vec![vec![None; num_of_positions]; num_of_cycles + 1];
+    best[0][(1 - MIN_SPRITE_POSITION) as usize] = Some((0, 0, Instruction::Noop));
+    let update = |best: &mut Vec<Vec<_>>, cycle: usize, idx: usize, candidate| {
+        let (count, _, _) = candidate;
+        match best[cycle][idx] {
+            Some((existing, _, _)) if existing <= count => {}
+            _ => best[cycle][idx] = Some(candidate),
+        }
+    };
+    for cycle in 0..num_of_cycles {
+        for idx in 0..num_of_positions {
+            let Some((count, _, _)) = best[cycle][idx] else {
+                continue;
+            };
+            if !can_draw(cycle, idx) {
+                continue;
+            }
+            update(
+                &mut best,
+                cycle + 1,
+                idx,
+                (count + 1, idx, Instruction::Noop),
+            );
+            if cycle + 1 < num_of_cycles && can_draw(cycle + 1, idx) {
+                for next_idx in 0..num_of_positions {
+                    let value = to_position(next_idx) - to_position(idx);
+                    let instruction = Instruction::Add(Register::X, value);
+                    update(
+                        &mut best,
+                        cycle + 2,
+                        next_idx,
+                        (count + 1, idx, instruction),
+                    );
+                }
+            }
+        }
     }
 
-    fn calculate_signal_strength_at_current_state(&self) -> i32 {
-        self.x * self.elapsed_ticks as i32
+    let Some(mut idx) = (0..num_of_positions)
+        .filter(|&idx| best[num_of_cycles][idx].is_some())
+        .min_by_key(|&idx| best[num_of_cycles][idx].unwrap().0)
+    else {
+        let cycle = (0..num_of_cycles)
+            .rev()
+            .find(|&cycle| best[cycle].iter().any(Option::is_some))
+            .unwrap();
+        return Err(format!(
+            "The pixel in row {} column {} cannot be drawn",
+            cycle / CRT_WIDTH,
+            cycle % CRT_WIDTH
+        ));
+    };
+
+    let mut program = Vec::new();
+    let mut cycle = num_of_cycles;
+    while cycle > 0 {
+        let (_, previous_idx, instruction) = best[cycle][idx].unwrap();
+        program.push(instruction);
+        cycle -= instruction.cycles();
+        idx = previous_idx;
     }
+    program.reverse();
+    Ok(program)
 }
 
+// counts down from `LINES`, moving the sprite by `STEP` each time
+const EXAMPLE_SOURCE: &str = "\
+.const LINES 3
+.const STEP 8
+
+    addy LINES
+loop:
+    addx STEP   ; move the sprite
+    suby 1
+    jnz y loop
+    halt
+    noop        ; never reached
+";
+
 fn main() {
-    let instructions = read_file_lines_as("input/day10.txt", |l| Instruction::from_str(l).unwrap());
+    let instructions = assemble(&read_file_to_string("input/day10.txt")).unwrap();
 
+    let mut signal_strength_meter = SignalStrengthMeter {
+        sum_of_interesting_signal_strengths: 0,
+    };
     let mut crt = Crt::new();
-    let sum_of_interesting_signal_strengths = crt.run_program(&instructions);
+    let mut tracer = Tracer { lines: Vec::new() };
+    let mut cpu = Cpu::new();
+    cpu.run(
+        &instructions,
+        &mut [&mut signal_strength_meter, &mut crt, &mut tracer],
+    )
+    .unwrap();
 
+    let sum_of_interesting_signal_strengths =
+        signal_strength_meter.sum_of_interesting_signal_strengths;
     println!(
         "The sum of the interesting signal strengths is {sum_of_interesting_signal_strengths}"
     );
 
     println!("The CRT rendered the following image:");
     print_2d_array(&crt.display);
+    let letters = Font::standard().recognise(&crt.display).unwrap();
+    println!("The CRT shows the letters {letters}");
+
+    println!("The first 10 cycles of the program were:");
+    for line in tracer.lines.iter().take(10) {
+        println!("{line}");
+    }
+
+    let disassembly = disassemble(&instructions).unwrap();
+    println!("The first 10 lines of the disassembled program are:");
+    for line in disassembly.lines().take(10) {
+        println!("{line}");
+    }
+    let round_trips = assemble(&disassembly).unwrap() == instructions;
+    println!("Assembling the disassembled program gives back the original: {round_trips}");
+
+    let example = assemble(EXAMPLE_SOURCE).unwrap();
+    println!("The example program disassembles to:");
+    print!("{}", disassemble(&example).unwrap());
+
+    let generated = generate_program_drawing(&crt.display).unwrap();
+    let mut generated_crt = Crt::new();
+    Cpu::new()
+        .run(&generated, &mut [&mut generated_crt])
+        .unwrap();
+    let draws_same_image = generated_crt.display == crt.display;
+    println!(
+        "The generated program of {} instructions draws the same image: {draws_same_image}",
+        generated.len()
+    );
 }
@@ -1,47 +1,386 @@
 use advent_of_code_2022::{read_file_to_string, EMPTY_LINE_PATTERN};
 use itertools::Itertools;
-use std::{collections::VecDeque, str::FromStr};
+use num_bigint::BigUint;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{self, Display, Formatter, Write},
+    fs::write,
+    str::FromStr,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Plus,
+    Minus,
+    Times,
+    Divide,
+}
+
+impl Operator {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '+' => Some(Self::Plus),
+            '-' => Some(Self::Minus),
+            '*' => Some(Self::Times),
+            '/' => Some(Self::Divide),
+            _ => None,
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Plus | Self::Minus => 1,
+            Self::Times | Self::Divide => 2,
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Plus => write!(f, "+"),
+            Self::Minus => write!(f, "-"),
+            Self::Times => write!(f, "*"),
+            Self::Divide => write!(f, "/"),
+        }
+    }
+}
 
 #[derive(Debug)]
-enum Operation {
-    Squared,
-    Plus(u64),
-    Times(u64),
+enum Expression {
+    Old,
+    Constant(u64),
+    BinaryOperation(Operator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Old,
+    Number(u64),
+    Operator(Operator),
+    OpeningParenthesis,
+    ClosingParenthesis,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let token = if c.is_whitespace() {
+            continue;
+        } else if c == '(' {
+            Token::OpeningParenthesis
+        } else if c == ')' {
+            Token::ClosingParenthesis
+        } else if let Some(operator) = Operator::from_char(c) {
+            Token::Operator(operator)
+        } else if c.is_ascii_digit() {
+            let mut end = idx + 1;
+            while let Some(&(next_idx, next)) = chars.peek() {
+                if !next.is_ascii_digit() {
+                    break;
+                }
+                end = next_idx + 1;
+                chars.next();
+            }
+            Token::Number(
+                s[idx..end]
+                    .parse()
+                    .map_err(|_| format!("Number '{}' is too large", &s[idx..end]))?,
+            )
+        } else if s[idx..].starts_with("old") {
+            chars.nth(1);
+            Token::Old
+        } else {
+            return Err(format!("Unexpected '{c}' at position {idx} in '{s}'"));
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/*
+ * recursive descent parser for the usual precedence rules:
+ * expression = term (('+' | '-') term)*
+ * term = factor (('*' | '/') factor)*
+ * factor = 'old' | number | '(' expression ')'
+ */
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self
+            .peek()
+            .ok_or("Unexpected end of expression".to_string())?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn parse_binary_operations(
+        &mut self,
+        operators: &[Operator],
+        parse_operand: fn(&mut Self) -> Result<Expression, String>,
+    ) -> Result<Expression, String> {
+        let mut lhs = parse_operand(self)?;
+        while let Some(Token::Operator(operator)) = self.peek() {
+            if !operators.contains(&operator) {
+                break;
+            }
+            self.position += 1;
+            let rhs = parse_operand(self)?;
+            lhs = Expression::BinaryOperation(operator, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        self.parse_binary_operations(&[Operator::Plus, Operator::Minus], Self::parse_term)
+    }
+
+    fn parse_term(&mut self) -> Result<Expression, String> {
+        self.parse_binary_operations(&[Operator::Times, Operator::Divide], Self::parse_factor)
+    }
+
+    fn parse_factor(&mut self) -> Result<Expression, String> {
+        match self.next()? {
+            Token::Old => Ok(Expression::Old),
+            Token::Number(n) => Ok(Expression::Constant(n)),
+            Token::OpeningParenthesis => {
+                let expression = self.parse_expression()?;
+                match self.next()? {
+                    Token::ClosingParenthesis => Ok(expression),
+                    token => Err(format!("Expected ')', found {token:?}")),
+                }
+            }
+            token => Err(format!("Unexpected {token:?}")),
+        }
+    }
 }
 
-impl FromStr for Operation {
+impl FromStr for Expression {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (operand, rhs) = s
-            .trim()
-            .split_once(' ')
-            .ok_or("Invalid format".to_string())?;
-        if rhs == "old" {
-            Ok(Self::Squared)
-        } else {
-            let rhs = rhs
-                .parse::<u64>()
-                .map_err(|_| "Invalid format".to_string())?;
-            match operand {
-                "+" => Ok(Self::Plus(rhs)),
-                "*" => Ok(Self::Times(rhs)),
-                _ => Err("Invalid format".to_string()),
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+        };
+        let expression = parser
+            .parse_expression()
+            .map_err(|e| format!("{e} in '{s}'"))?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(token) => Err(format!("Unexpected {token:?} in '{s}'")),
+        }
+    }
+}
+
+// only puts parentheses where the precedence rules require them
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Old => write!(f, "old"),
+            Self::Constant(n) => write!(f, "{n}"),
+            Self::BinaryOperation(operator, lhs, rhs) => {
+                match lhs.as_ref() {
+                    Self::BinaryOperation(lhs_operator, ..)
+                        if lhs_operator.precedence() < operator.precedence() =>
+                    {
+                        write!(f, "({lhs})")?
+                    }
+                    _ => write!(f, "{lhs}")?,
+                }
+                write!(f, " {operator} ")?;
+                match rhs.as_ref() {
+                    Self::BinaryOperation(rhs_operator, ..)
+                        if rhs_operator.precedence() <= operator.precedence() =>
+                    {
+                        write!(f, "({rhs})")
+                    }
+                    _ => write!(f, "{rhs}"),
+                }
             }
         }
     }
 }
 
+impl Expression {
+    /*
+     * when a modulus is given, the result is only correct modulo it, which is enough to keep
+     * divisibility information, but only as long as there is no division in the expression
+     */
+    fn evaluate<W: WorryLevel>(&self, old: &W, modulus: Option<u64>) -> Result<W, String> {
+        let (operator, lhs, rhs) = match self {
+            Self::Old => return Ok(old.clone()),
+            Self::Constant(n) => {
+                let n = W::from_u64(*n);
+                return Ok(modulus.map_or(n.clone(), |m| n.reduce(m)));
+            }
+            Self::BinaryOperation(operator, lhs, rhs) => (
+                operator,
+                lhs.evaluate(old, modulus)?,
+                rhs.evaluate(old, modulus)?,
+            ),
+        };
+        let result = match (operator, modulus) {
+            // subtracting modulo `m` is adding the additive inverse
+            (Operator::Minus, Some(m)) => W::from_u64(m)
+                .apply(Operator::Minus, &rhs)
+                .and_then(|inverse| lhs.apply(Operator::Plus, &inverse)),
+            _ => lhs.apply(*operator, &rhs),
+        }
+        .ok_or(format!(
+            "Could not calculate {lhs} {operator} {rhs}, as it is out of range"
+        ))?;
+        Ok(modulus.map_or(result.clone(), |m| result.reduce(m)))
+    }
+
+    // describes the operation the way the puzzle does, e.g. `increases by 6`
+    fn describe(&self) -> String {
+        match self {
+            Self::BinaryOperation(operator, lhs, rhs) if matches!(lhs.as_ref(), Self::Old) => {
+                match (operator, rhs.as_ref()) {
+                    (Operator::Times, Self::Old) => "is multiplied by itself".to_string(),
+                    (Operator::Plus, Self::Constant(n)) => format!("increases by {n}"),
+                    (Operator::Minus, Self::Constant(n)) => format!("decreases by {n}"),
+                    (Operator::Times, Self::Constant(n)) => format!("is multiplied by {n}"),
+                    (Operator::Divide, Self::Constant(n)) => format!("is divided by {n}"),
+                    _ => format!("becomes {self}"),
+                }
+            }
+            _ => format!("becomes {self}"),
+        }
+    }
+
+    fn contains_division(&self) -> bool {
+        match self {
+            Self::Old | Self::Constant(_) => false,
+            Self::BinaryOperation(operator, lhs, rhs) => {
+                *operator == Operator::Divide || lhs.contains_division() || rhs.contains_division()
+            }
+        }
+    }
+}
+
+// a representation of worry levels, where `apply` returns `None` if the result is out of range
+trait WorryLevel: Clone + Display {
+    fn from_u64(n: u64) -> Self;
+    fn apply(&self, operator: Operator, rhs: &Self) -> Option<Self>;
+    fn reduce(&self, modulus: u64) -> Self;
+    fn is_divisible_by(&self, divisor: u64) -> bool;
+}
+
+impl WorryLevel for u64 {
+    fn from_u64(n: u64) -> Self {
+        n
+    }
+
+    fn apply(&self, operator: Operator, rhs: &Self) -> Option<Self> {
+        match operator {
+            Operator::Plus => self.checked_add(*rhs),
+            Operator::Minus => self.checked_sub(*rhs),
+            Operator::Times => self.checked_mul(*rhs),
+            Operator::Divide => self.checked_div(*rhs),
+        }
+    }
+
+    fn reduce(&self, modulus: u64) -> Self {
+        self % modulus
+    }
+
+    fn is_divisible_by(&self, divisor: u64) -> bool {
+        self.checked_rem(divisor) == Some(0)
+    }
+}
+
+// never overflows, so it can be used to check the results of the other representations
+impl WorryLevel for BigUint {
+    fn from_u64(n: u64) -> Self {
+        BigUint::from(n)
+    }
+
+    fn apply(&self, operator: Operator, rhs: &Self) -> Option<Self> {
+        match operator {
+            Operator::Plus => Some(self + rhs),
+            Operator::Minus => (self >= rhs).then(|| self - rhs),
+            Operator::Times => Some(self * rhs),
+            Operator::Divide => (*rhs != BigUint::from(0u32)).then(|| self / rhs),
+        }
+    }
+
+    fn reduce(&self, modulus: u64) -> Self {
+        self % modulus
+    }
+
+    fn is_divisible_by(&self, divisor: u64) -> bool {
+        self % divisor == BigUint::from(0u32)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WorryMode {
+    // the puzzle's worry relief, dividing by 3 after each inspection
+    DividedByThree,
+    KeptAsRemainders,
+    Exact,
+}
+
 #[derive(Debug)]
-struct Monkey {
-    items: VecDeque<u64>,
-    operation: Operation,
+struct Monkey<W> {
+    items: VecDeque<W>,
+    operation: Expression,
     divisor: u64,
     throw_to_if_true_id: usize,
     throw_to_if_false_id: usize,
     num_of_inspections: u64,
 }
 
+struct Inspection<W> {
+    new_worry_level: W,
+    adjusted_worry_level: W,
+    is_divisible: bool,
+    target_id: usize,
+}
+
+impl<W: WorryLevel> Monkey<W> {
+    fn inspect(
+        &self,
+        worry_level: &W,
+        mode: WorryMode,
+        modulus: Option<u64>,
+    ) -> Result<Inspection<W>, String> {
+        let new_worry_level = self.operation.evaluate(worry_level, modulus)?;
+        let adjusted_worry_level = if mode == WorryMode::DividedByThree {
+            // divide by `3` to relief some worry
+            new_worry_level
+                .apply(Operator::Divide, &W::from_u64(3))
+                .unwrap()
+        } else {
+            new_worry_level.clone()
+        };
+
+        let is_divisible = adjusted_worry_level.is_divisible_by(self.divisor);
+        let target_id = if is_divisible {
+            self.throw_to_if_true_id
+        } else {
+            self.throw_to_if_false_id
+        };
+        Ok(Inspection {
+            new_worry_level,
+            adjusted_worry_level,
+            is_divisible,
+            target_id,
+        })
+    }
+}
+
 fn next_line_or_err<'a>(i: &mut impl Iterator<Item = &'a str>) -> Result<&'a str, String> {
     i.next().ok_or("Invalid format".to_string())
 }
@@ -53,7 +392,7 @@ fn parse_number_at_last_word_of<N: FromStr>(s: &str) -> Result<N, String> {
         .ok_or("Invalid format".to_string())
 }
 
-impl FromStr for Monkey {
+impl<W: WorryLevel> FromStr for Monkey<W> {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -67,15 +406,15 @@ impl FromStr for Monkey {
             .ok_or("Invalid format".to_string())?;
         let items = items_str
             .split(", ")
-            .map(|n| n.parse::<u64>())
+            .map(|n| n.parse::<u64>().map(W::from_u64))
             .collect::<Result<VecDeque<_>, _>>()
             .map_err(|_| "Invalid format".to_string())?;
 
         let third_line = next_line_or_err(&mut lines_iter)?;
         let (_, op_str) = third_line
-            .split_once("old ")
+            .split_once("new = ")
             .ok_or("Invalid format".to_string())?;
-        let operation = Operation::from_str(op_str)?;
+        let operation = Expression::from_str(op_str)?;
 
         let fourth_line = next_line_or_err(&mut lines_iter)?;
         let divisor = parse_number_at_last_word_of(fourth_line)?;
@@ -97,14 +436,14 @@ impl FromStr for Monkey {
     }
 }
 
-fn create_monkeys(input: &str) -> Vec<Monkey> {
+fn create_monkeys<W: WorryLevel>(input: &str) -> Vec<Monkey<W>> {
     input
         .split(EMPTY_LINE_PATTERN)
         .map(|s| Monkey::from_str(s).unwrap())
         .collect()
 }
 
-fn calculate_monkey_business(monkeys: &[Monkey]) -> u64 {
+fn calculate_monkey_business<W>(monkeys: &[Monkey<W>]) -> u64 {
     monkeys
         .iter()
         .map(|m| m.num_of_inspections)
@@ -113,69 +452,360 @@ fn calculate_monkey_business(monkeys: &[Monkey]) -> u64 {
         .product()
 }
 
-fn simulate_round(monkeys: &mut [Monkey], use_remainders: bool) {
+fn get_modulus<W>(monkeys: &[Monkey<W>], mode: WorryMode) -> Option<u64> {
     let product_of_all_divisors = monkeys.iter().map(|m| m.divisor).product::<u64>();
+    // if using remainders, constrain the worry to be less than the product of
+    // all remainders, as this retains all divisibility information inside the result
+    // for all divisors that the monkeys have
+    (mode == WorryMode::KeptAsRemainders).then_some(product_of_all_divisors)
+}
+
+// the remainder trick does not work with division, as `(a mod m) / b` is not `(a / b) mod m`
+fn check_remainders_are_usable<W>(monkeys: &[Monkey<W>]) -> Result<(), String> {
+    let dividing_monkeys = monkeys
+        .iter()
+        .positions(|m| m.operation.contains_division())
+        .collect::<Vec<_>>();
+    if dividing_monkeys.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Worry levels cannot be kept as remainders, as monkeys {dividing_monkeys:?} divide them"
+        ))
+    }
+}
+
+// when given a trace, every inspection is logged to it the way the puzzle describes them
+fn simulate_round<W: WorryLevel>(
+    monkeys: &mut [Monkey<W>],
+    mode: WorryMode,
+    mut trace: Option<&mut String>,
+) -> Result<(), String> {
+    let modulus = get_modulus(monkeys, mode);
 
     let num_of_monkeys = monkeys.len();
     for i in 0..num_of_monkeys {
+        if let Some(trace) = trace.as_mut() {
+            writeln!(trace, "Monkey {i}:").unwrap();
+        }
         while let Some(old_worry_level) = monkeys[i].items.pop_front() {
             monkeys[i].num_of_inspections += 1;
-            let new_worry_level = match monkeys[i].operation {
-                Operation::Squared => old_worry_level * old_worry_level,
-                Operation::Plus(rhs) => old_worry_level + rhs,
-                Operation::Times(rhs) => old_worry_level * rhs,
-            };
-            let adjusted_worry_level = if use_remainders {
-                // if using remainders, constrain the worry to be less than the product of
-                // all remainders, as this retains all divisibility information inside the result
-                // for all divisors that the monkeys have
-                new_worry_level % product_of_all_divisors
-            } else {
-                // if not using remainders, divide by `3` to relief some worry
-                new_worry_level / 3
-            };
+            let Inspection {
+                new_worry_level,
+                adjusted_worry_level,
+                is_divisible,
+                target_id,
+            } = monkeys[i]
+                .inspect(&old_worry_level, mode, modulus)
+                .map_err(|e| format!("Monkey {i}: {e}"))?;
 
-            let true_id = monkeys[i].throw_to_if_true_id;
-            let false_id = monkeys[i].throw_to_if_false_id;
+            if let Some(trace) = trace.as_mut() {
+                let monkey = &monkeys[i];
+                writeln!(
+                    trace,
+                    "  Monkey inspects an item with a worry level of {old_worry_level}."
+                )
+                .unwrap();
+                writeln!(
+                    trace,
+                    "    Worry level {} to {new_worry_level}.",
+                    monkey.operation.describe()
+                )
+                .unwrap();
+                if mode == WorryMode::DividedByThree {
+                    writeln!(trace, "    Monkey gets bored with item. Worry level is divided by 3 to {adjusted_worry_level}.").unwrap();
+                }
+                let not = if is_divisible { "" } else { "not " };
+                writeln!(
+                    trace,
+                    "    Current worry level is {not}divisible by {}.",
+                    monkey.divisor
+                )
+                .unwrap();
+                writeln!(
+                    trace,
+                    "    Item with worry level {adjusted_worry_level} is thrown to monkey {target_id}."
+                )
+                .unwrap();
+            }
 
-            let monkey_to_throw_to = if adjusted_worry_level % monkeys[i].divisor == 0 {
-                monkeys.get_mut(true_id).unwrap()
-            } else {
-                monkeys.get_mut(false_id).unwrap()
-            };
-            monkey_to_throw_to.items.push_back(adjusted_worry_level);
+            monkeys
+                .get_mut(target_id)
+                .ok_or(format!("Monkey {i} throws to missing monkey {target_id}"))?
+                .items
+                .push_back(adjusted_worry_level);
         }
     }
+
+    Ok(())
 }
 
-fn simulate_game_for_20_rounds_with_worry_relief(input: &str) -> u64 {
-    let mut monkeys = create_monkeys(input);
+fn simulate_game_for_20_rounds_with_worry_relief(input: &str) -> Result<u64, String> {
+    let mut monkeys = create_monkeys::<u64>(input);
 
     for _ in 0..20 {
-        simulate_round(&mut monkeys, false);
+        simulate_round(&mut monkeys, WorryMode::DividedByThree, None)?;
     }
 
-    calculate_monkey_business(&monkeys)
+    Ok(calculate_monkey_business(&monkeys))
 }
 
-fn simulate_game_for_10_000_rounds_without_worry_relief(input: &str) -> u64 {
-    let mut monkeys = create_monkeys(input);
+fn simulate_game_for_10_000_rounds_without_worry_relief(input: &str) -> Result<u64, String> {
+    let mut monkeys = create_monkeys::<u64>(input);
+    check_remainders_are_usable(&monkeys)?;
 
     for _ in 0..10_000 {
-        simulate_round(&mut monkeys, true);
+        simulate_round(&mut monkeys, WorryMode::KeptAsRemainders, None)?;
+    }
+
+    Ok(calculate_monkey_business(&monkeys))
+}
+
+/*
+ * simulates the rounds without worry relief both with remainders and with exact worry levels,
+ * returning whether the monkeys inspected the same number of items;
+ * exact worry levels grow very quickly, so this is only feasible for a few rounds
+ */
+fn verify_remainders_against_exact_worry_levels(
+    input: &str,
+    num_of_rounds: usize,
+) -> Result<bool, String> {
+    let mut monkeys = create_monkeys::<u64>(input);
+    check_remainders_are_usable(&monkeys)?;
+    let mut exact_monkeys = create_monkeys::<BigUint>(input);
+
+    for _ in 0..num_of_rounds {
+        simulate_round(&mut monkeys, WorryMode::KeptAsRemainders, None)?;
+        simulate_round(&mut exact_monkeys, WorryMode::Exact, None)?;
+    }
+
+    let inspections = monkeys.iter().map(|m| m.num_of_inspections);
+    let exact_inspections = exact_monkeys.iter().map(|m| m.num_of_inspections);
+    Ok(inspections.eq(exact_inspections))
+}
+
+fn trace_rounds_with_worry_relief(input: &str, num_of_rounds: usize) -> Result<String, String> {
+    let mut monkeys = create_monkeys::<u64>(input);
+    let mut trace = String::new();
+    for round in 1..=num_of_rounds {
+        simulate_round(&mut monkeys, WorryMode::DividedByThree, Some(&mut trace))?;
+        writeln!(
+            trace,
+            "\nAfter round {round}, the monkeys are holding items with these worry levels:"
+        )
+        .unwrap();
+        for (i, monkey) in monkeys.iter().enumerate() {
+            writeln!(trace, "Monkey {i}: {}", monkey.items.iter().join(", ")).unwrap();
+        }
+        writeln!(trace).unwrap();
+    }
+    Ok(trace)
+}
+
+/*
+ * items never affect each other, so each one can be followed on its own; records which monkeys
+ * inspect the item in each round, until either enough rounds have been recorded or the item is
+ * in the same state at the start of a round as in an earlier one, after which it keeps repeating
+ * the rounds in between forever
+ */
+struct ItemJourney {
+    inspections_by_round: Vec<Vec<usize>>,
+    // the first round of the repeating part and its length
+    cycle: Option<(usize, usize)>,
+}
+
+impl ItemJourney {
+    fn follow(
+        monkeys: &[Monkey<u64>],
+        starting_monkey: usize,
+        starting_worry_level: u64,
+        mode: WorryMode,
+        max_num_of_rounds: usize,
+    ) -> Result<Self, String> {
+        let modulus = get_modulus(monkeys, mode);
+        let mut state = (starting_monkey, starting_worry_level);
+        let mut first_seen_at = HashMap::new();
+        let mut inspections_by_round = Vec::new();
+        while inspections_by_round.len() < max_num_of_rounds {
+            let round = inspections_by_round.len();
+            if let Some(&cycle_start) = first_seen_at.get(&state) {
+                return Ok(Self {
+                    inspections_by_round,
+                    cycle: Some((cycle_start, round - cycle_start)),
+                });
+            }
+            first_seen_at.insert(state, round);
+
+            // the item is inspected again in the same round if it is thrown to a monkey that
+            // has not had its turn yet
+            let (mut monkey, mut worry_level) = state;
+            let mut inspections = Vec::new();
+            loop {
+                inspections.push(monkey);
+                let inspection = monkeys
+                    .get(monkey)
+                    .ok_or(format!("Missing monkey {monkey}"))?
+                    .inspect(&worry_level, mode, modulus)
+                    .map_err(|e| format!("Monkey {monkey}: {e}"))?;
+                worry_level = inspection.adjusted_worry_level;
+                let previous_monkey = monkey;
+                monkey = inspection.target_id;
+                if monkey <= previous_monkey {
+                    break;
+                }
+            }
+            inspections_by_round.push(inspections);
+            state = (monkey, worry_level);
+        }
+        Ok(Self {
+            inspections_by_round,
+            cycle: None,
+        })
     }
 
-    calculate_monkey_business(&monkeys)
+    fn count_inspections_in_rounds(
+        &self,
+        rounds: impl Iterator<Item = usize>,
+        num_of_monkeys: usize,
+    ) -> Vec<u64> {
+        let mut inspections = vec![0; num_of_monkeys];
+        for round in rounds {
+            for &monkey in &self.inspections_by_round[round] {
+                inspections[monkey] += 1;
+            }
+        }
+        inspections
+    }
+
+    // how many times each monkey inspects the item during the first `num_of_rounds` rounds
+    fn count_inspections(&self, num_of_rounds: u64, num_of_monkeys: usize) -> Vec<u64> {
+        let recorded_rounds = self.inspections_by_round.len() as u64;
+        let (cycle_start, cycle_len) = match self.cycle {
+            Some(cycle) if num_of_rounds > recorded_rounds => cycle,
+            // without a cycle, only the recorded rounds can be counted
+            _ => {
+                let num_of_rounds = num_of_rounds.min(recorded_rounds) as usize;
+                return self.count_inspections_in_rounds(0..num_of_rounds, num_of_monkeys);
+            }
+        };
+
+        let num_of_cycled_rounds = num_of_rounds - cycle_start as u64;
+        let num_of_full_cycles = num_of_cycled_rounds / cycle_len as u64;
+        let num_of_remaining_rounds = (num_of_cycled_rounds % cycle_len as u64) as usize;
+        let before_cycle = self.count_inspections_in_rounds(0..cycle_start, num_of_monkeys);
+        let cycle =
+            self.count_inspections_in_rounds(cycle_start..cycle_start + cycle_len, num_of_monkeys);
+        let remaining = self.count_inspections_in_rounds(
+            cycle_start..cycle_start + num_of_remaining_rounds,
+            num_of_monkeys,
+        );
+        (0..num_of_monkeys)
+            .map(|i| before_cycle[i] + num_of_full_cycles * cycle[i] + remaining[i])
+            .collect()
+    }
+}
+
+/*
+ * counts the inspections of each monkey by following every item until its state repeats,
+ * so the number of rounds can be far more than what could be simulated round by round;
+ * as worry levels only repeat if they are bounded, this only works with remainders
+ */
+fn fast_forward_inspections(
+    input: &str,
+    num_of_rounds: u64,
+    max_num_of_followed_rounds: usize,
+) -> Result<Vec<u64>, String> {
+    let monkeys = create_monkeys::<u64>(input);
+    check_remainders_are_usable(&monkeys)?;
+
+    let mut inspections = vec![0; monkeys.len()];
+    for (i, monkey) in monkeys.iter().enumerate() {
+        for &worry_level in &monkey.items {
+            let journey = ItemJourney::follow(
+                &monkeys,
+                i,
+                worry_level,
+                WorryMode::KeptAsRemainders,
+                max_num_of_followed_rounds,
+            )?;
+            if journey.cycle.is_none()
+                && (journey.inspections_by_round.len() as u64) < num_of_rounds
+            {
+                return Err(format!(
+                    "The item with worry level {worry_level} held by monkey {i} did not repeat its state in {max_num_of_followed_rounds} rounds"
+                ));
+            }
+            let item_inspections = journey.count_inspections(num_of_rounds, monkeys.len());
+            for (total, n) in inspections.iter_mut().zip(item_inspections) {
+                *total += n;
+            }
+        }
+    }
+    Ok(inspections)
+}
+
+fn calculate_monkey_business_from_inspections(inspections: &[u64]) -> u128 {
+    inspections
+        .iter()
+        .sorted_by(|a, b| b.cmp(a))
+        .take(2)
+        .map(|&n| n as u128)
+        .product()
 }
 
 fn main() {
     let input = read_file_to_string("input/day11.txt");
 
     let level_of_monkey_business_after_20_rounds =
-        simulate_game_for_20_rounds_with_worry_relief(&input);
+        simulate_game_for_20_rounds_with_worry_relief(&input).unwrap();
     println!("The level of monkey business after 20 rounds with worry relief is {level_of_monkey_business_after_20_rounds}");
 
     let level_of_monkey_business_after_10_000_rounds =
-        simulate_game_for_10_000_rounds_without_worry_relief(&input);
+        simulate_game_for_10_000_rounds_without_worry_relief(&input).unwrap();
     println!("The level of monkey business after 10,000 rounds without worry relief is {level_of_monkey_business_after_10_000_rounds}");
+
+    let num_of_verified_rounds = 8;
+    let remainders_match_exact_worry_levels =
+        verify_remainders_against_exact_worry_levels(&input, num_of_verified_rounds).unwrap();
+    println!("Keeping remainders gives the same inspection counts as exact worry levels for the first {num_of_verified_rounds} rounds: {remainders_match_exact_worry_levels}");
+
+    let trace = trace_rounds_with_worry_relief(&input, 20).unwrap();
+    write("day11_trace.txt", trace).unwrap();
+    println!("The trace of the first 20 rounds with worry relief was written to day11_trace.txt");
+
+    let monkeys = create_monkeys::<u64>(&input);
+    let first_item_journey = ItemJourney::follow(
+        &monkeys,
+        0,
+        monkeys[0].items[0],
+        WorryMode::DividedByThree,
+        20,
+    )
+    .unwrap();
+    let first_item_inspections = first_item_journey.count_inspections(20, monkeys.len());
+    println!("During the first 20 rounds with worry relief, the first item was inspected by the monkeys this many times: {first_item_inspections:?}");
+
+    let first_item_journey_without_relief = ItemJourney::follow(
+        &monkeys,
+        0,
+        monkeys[0].items[0],
+        WorryMode::KeptAsRemainders,
+        usize::MAX,
+    )
+    .unwrap();
+    if let Some((cycle_start, cycle_len)) = first_item_journey_without_relief.cycle {
+        println!("Without worry relief, the first item repeats the same {cycle_len} rounds from round {} onwards", cycle_start + 1);
+    }
+
+    let inspections_after_10_000_rounds =
+        fast_forward_inspections(&input, 10_000, 1_000_000).unwrap();
+    let fast_forwarded_level_of_monkey_business_after_10_000_rounds =
+        calculate_monkey_business_from_inspections(&inspections_after_10_000_rounds);
+    println!("Following the items one by one gives a level of monkey business of {fast_forwarded_level_of_monkey_business_after_10_000_rounds} after 10,000 rounds");
+
+    let inspections_after_trillion_rounds =
+        fast_forward_inspections(&input, 1_000_000_000_000, 1_000_000).unwrap();
+    let level_of_monkey_business_after_trillion_rounds =
+        calculate_monkey_business_from_inspections(&inspections_after_trillion_rounds);
+    println!("The level of monkey business after 10^12 rounds without worry relief is {level_of_monkey_business_after_trillion_rounds}");
 }
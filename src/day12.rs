@@ -1,6 +1,13 @@
-use advent_of_code_2022::{parse_2d_char_grid, read_file_to_string};
+use advent_of_code_2022::{
+    heatmap_colour, parse_2d_char_grid, read_file_to_string, render_2d_array_as,
+    write_2d_array_as_ppm,
+};
 use array2d::Array2D;
-use std::{collections::HashSet, str::FromStr};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+    str::FromStr,
+};
 
 type Coords = (usize, usize);
 
@@ -9,10 +16,92 @@ enum Direction {
     Backwards,
 }
 
+// decides which neighbouring tiles can be climbed to and how much that costs
+trait ClimbingRule {
+    // returns `None` if a tile of height `to` cannot be climbed to from a tile of height `from`
+    fn cost(&self, from: u8, to: u8) -> Option<usize>;
+}
+
+// at most this much higher, any amount lower; the puzzle uses `MaxAscent(1)`
+struct MaxAscent(u8);
+
+impl ClimbingRule for MaxAscent {
+    fn cost(&self, from: u8, to: u8) -> Option<usize> {
+        (to.saturating_sub(from) <= self.0).then_some(1)
+    }
+}
+
+// at most this much lower, any amount higher
+struct MaxDescent(u8);
+
+impl ClimbingRule for MaxDescent {
+    fn cost(&self, from: u8, to: u8) -> Option<usize> {
+        (from.saturating_sub(to) <= self.0).then_some(1)
+    }
+}
+
+// any tile can be climbed to, but each unit of height change costs extra
+struct HeightChangeCost {
+    cost_per_unit: usize,
+}
+
+impl ClimbingRule for HeightChangeCost {
+    fn cost(&self, from: u8, to: u8) -> Option<usize> {
+        Some(1 + self.cost_per_unit * from.abs_diff(to) as usize)
+    }
+}
+
+enum Movement {
+    Orthogonal,
+    // diagonal steps cost the same as orthogonal ones
+    OrthogonalAndDiagonal,
+}
+
+impl Movement {
+    fn get_offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Self::Orthogonal => &[(0, 1), (0, -1), (-1, 0), (1, 0)],
+            Self::OrthogonalAndDiagonal => &[
+                (0, 1),
+                (0, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, -1),
+                (-1, 1),
+                (1, -1),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+struct ShortestPaths {
+    // with unit costs, these are the lengths of the paths
+    costs: Array2D<usize>,
+    // the tile each tile was reached from on its shortest path
+    previous: Array2D<Option<Coords>>,
+}
+
+impl ShortestPaths {
+    // returns the tiles from `pos` back to where the search started, if it could be reached
+    fn trace_back_from(&self, pos: &Coords) -> Option<Vec<Coords>> {
+        if *self.costs.get(pos.0, pos.1)? == usize::MAX {
+            return None;
+        }
+        let mut route = vec![*pos];
+        while let Some(previous) = *self.previous.get(route.last()?.0, route.last()?.1)? {
+            route.push(previous);
+        }
+        Some(route)
+    }
+}
+
 struct Valley {
     height_map: Array2D<u8>,
     start: Coords,
     finish: Coords,
+    rule: Box<dyn ClimbingRule>,
+    movement: Movement,
 }
 
 impl FromStr for Valley {
@@ -55,14 +144,49 @@ impl FromStr for Valley {
             height_map,
             start,
             finish,
+            rule: Box::new(MaxAscent(1)),
+            movement: Movement::Orthogonal,
         })
     }
 }
 
 impl Valley {
+    fn with_rules(self, rule: impl ClimbingRule + 'static, movement: Movement) -> Self {
+        Self {
+            rule: Box::new(rule),
+            movement,
+            ..self
+        }
+    }
+
     fn find_length_of_shortest_path_from_start_to_finish(&self) -> usize {
         let shortest_paths = self.find_shortest_paths_from(&self.start, &Direction::Forwards);
-        *shortest_paths.get(self.finish.0, self.finish.1).unwrap()
+        *shortest_paths
+            .costs
+            .get(self.finish.0, self.finish.1)
+            .unwrap()
+    }
+
+    fn find_shortest_route_from_start_to_finish(&self) -> Option<Vec<Coords>> {
+        let shortest_paths = self.find_shortest_paths_from(&self.start, &Direction::Forwards);
+        let mut route = shortest_paths.trace_back_from(&self.finish)?;
+        route.reverse();
+        Some(route)
+    }
+
+    // searching backwards from the finish means tracing back leads towards the finish
+    fn find_shortest_route_from_lowest_points_to_finish(&self) -> Option<Vec<Coords>> {
+        let shortest_paths_from_finish =
+            self.find_shortest_paths_from(&self.finish, &Direction::Backwards);
+        self.height_map
+            .indices_row_major()
+            .filter(|&(r, c)| *self.height_map.get(r, c).unwrap() == 0)
+            .filter_map(|pos| {
+                let cost = *shortest_paths_from_finish.costs.get(pos.0, pos.1).unwrap();
+                Some((cost, shortest_paths_from_finish.trace_back_from(&pos)?))
+            })
+            .min_by_key(|(cost, _)| *cost)
+            .map(|(_, route)| route)
     }
 
     fn find_length_of_shortest_path_from_lowest_points_to_finish(&self) -> usize {
@@ -74,7 +198,7 @@ impl Valley {
                 let height = *self.height_map.get(r, c).unwrap();
 
                 if height == 0 {
-                    let path = *shortest_paths_from_finish.get(r, c).unwrap();
+                    let path = *shortest_paths_from_finish.costs.get(r, c).unwrap();
                     Some(path)
                 } else {
                     None
@@ -84,87 +208,57 @@ impl Valley {
             .unwrap()
     }
 
-    fn find_shortest_paths_from(&self, start: &Coords, dir: &Direction) -> Array2D<usize> {
-        let mut shortest_path_until = Array2D::filled_with(
-            usize::MAX,
-            self.height_map.num_rows(),
-            self.height_map.num_columns(),
-        );
-        shortest_path_until.set(start.0, start.1, 0).unwrap();
-
-        let mut coords_under_improvement = HashSet::new();
-        coords_under_improvement.insert(*start);
-
-        while !coords_under_improvement.is_empty() {
-            coords_under_improvement =
-                self.try_improve_paths(&mut shortest_path_until, coords_under_improvement, dir);
-        }
-
-        shortest_path_until
-    }
+    // Dijkstra's algorithm, as steps can have different costs depending on the climbing rule
+    fn find_shortest_paths_from(&self, start: &Coords, dir: &Direction) -> ShortestPaths {
+        let num_rows = self.height_map.num_rows();
+        let num_columns = self.height_map.num_columns();
+        let mut shortest_path_until = ShortestPaths {
+            costs: Array2D::filled_with(usize::MAX, num_rows, num_columns),
+            previous: Array2D::filled_with(None, num_rows, num_columns),
+        };
+        shortest_path_until.costs.set(start.0, start.1, 0).unwrap();
 
-    fn try_improve_paths(
-        &self,
-        shortest_path_until: &mut Array2D<usize>,
-        coords_under_improvement: HashSet<Coords>,
-        dir: &Direction,
-    ) -> HashSet<Coords> {
-        let mut changed = HashSet::new();
-        for (row, column) in coords_under_improvement {
-            let shortest_path_len = *shortest_path_until.get(row, column).unwrap();
-            let neighbours = self.get_neighbours(&(row, column), dir);
-            for n in &neighbours {
-                let neighbour_shortest_len = *shortest_path_until.get(n.0, n.1).unwrap();
-
-                let potential = shortest_path_len + 1;
-                if potential < neighbour_shortest_len {
-                    shortest_path_until.set(n.0, n.1, potential).unwrap();
-                    changed.insert(*n);
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((0, *start)));
+        while let Some(Reverse((cost, pos))) = queue.pop() {
+            if cost > *shortest_path_until.costs.get(pos.0, pos.1).unwrap() {
+                // already reached more cheaply
+                continue;
+            }
+            for (n, step_cost) in self.get_neighbours(&pos, dir) {
+                let potential = cost + step_cost;
+                if potential < *shortest_path_until.costs.get(n.0, n.1).unwrap() {
+                    shortest_path_until.costs.set(n.0, n.1, potential).unwrap();
+                    shortest_path_until
+                        .previous
+                        .set(n.0, n.1, Some(pos))
+                        .unwrap();
+                    queue.push(Reverse((potential, n)));
                 }
             }
         }
 
-        changed
-    }
-
-    /*
-     * checks whether a tile is reachable from another tile according to the rule:
-     * a tile is reachable from the current if it's at most 1 higher
-     * e.g. if `current = 5`, then neighbour can be `0..=6`
-     */
-    fn can_reach(current_height: u8, neighbour_height: u8) -> bool {
-        current_height + 1 >= neighbour_height
+        shortest_path_until
     }
 
-    fn get_neighbours(&self, pos: &Coords, dir: &Direction) -> Vec<Coords> {
-        let row = pos.0 as isize;
-        let column = pos.1 as isize;
-        let neighbour_positions = [
-            (row, column + 1),
-            (row, column - 1),
-            (row - 1, column),
-            (row + 1, column),
-        ];
-
+    // returns the neighbours that can be stepped to and the cost of each step
+    fn get_neighbours(&self, pos: &Coords, dir: &Direction) -> Vec<(Coords, usize)> {
         let height = self.get_height_at(pos.0, pos.1).unwrap();
 
-        neighbour_positions
+        self.movement
+            .get_offsets()
             .iter()
-            .filter_map(|(r, c)| {
-                if *r >= 0 && *c >= 0 {
-                    let r = *r as usize;
-                    let c = *c as usize;
-                    self.get_height_at(r, c)
-                        .filter(|&neighbour_height| match dir {
-                            // when going forwards check what neighbouring tiles can the current tile reach
-                            Direction::Forwards => Self::can_reach(height, neighbour_height),
-                            // when going backwards, check the opposite: what neighbouring tiles can reach the current tile
-                            Direction::Backwards => Self::can_reach(neighbour_height, height),
-                        })
-                        .and(Some((r, c)))
-                } else {
-                    None
-                }
+            .filter_map(|(dr, dc)| {
+                let r = pos.0.checked_add_signed(*dr)?;
+                let c = pos.1.checked_add_signed(*dc)?;
+                let neighbour_height = self.get_height_at(r, c)?;
+                let cost = match dir {
+                    // when going forwards check what neighbouring tiles can the current tile reach
+                    Direction::Forwards => self.rule.cost(height, neighbour_height),
+                    // when going backwards, check the opposite: what neighbouring tiles can reach the current tile
+                    Direction::Backwards => self.rule.cost(neighbour_height, height),
+                }?;
+                Some(((r, c), cost))
             })
             .collect()
     }
@@ -172,6 +266,62 @@ impl Valley {
     fn get_height_at(&self, row: usize, column: usize) -> Option<u8> {
         self.height_map.get(row, column).copied()
     }
+
+    fn get_height_map_as_chars(&self) -> Array2D<char> {
+        let mut chars = Array2D::from_iter_row_major(
+            self.height_map
+                .elements_row_major_iter()
+                .map(|h| (b'a' + h) as char),
+            self.height_map.num_rows(),
+            self.height_map.num_columns(),
+        )
+        .unwrap();
+        chars.set(self.start.0, self.start.1, 'S').unwrap();
+        chars.set(self.finish.0, self.finish.1, 'E').unwrap();
+        chars
+    }
+
+    // draws the route over the height map like the puzzle does, with `E` marking the finish
+    fn render_route(&self, route: &[Coords]) -> String {
+        let mut chars = self.get_height_map_as_chars();
+        for (from, to) in route.iter().zip(route.iter().skip(1)) {
+            let arrow = match (
+                to.0 as isize - from.0 as isize,
+                to.1 as isize - from.1 as isize,
+            ) {
+                (0, 1) => '>',
+                (0, -1) => '<',
+                (1, 0) => 'v',
+                (-1, 0) => '^',
+                (-1, -1) => '↖',
+                (-1, 1) => '↗',
+                (1, -1) => '↙',
+                (1, 1) => '↘',
+                _ => '?',
+            };
+            chars.set(from.0, from.1, arrow).unwrap();
+        }
+        render_2d_array_as(&chars, char::to_string)
+    }
+
+    // colours tiles by their height, with the route drawn in blue
+    fn export_route_as_ppm(&self, route: &[Coords], path: &str) -> std::io::Result<()> {
+        let route = route.iter().collect::<HashSet<_>>();
+        let mut colours = Array2D::filled_with(
+            [0, 0, 0],
+            self.height_map.num_rows(),
+            self.height_map.num_columns(),
+        );
+        for (r, c) in self.height_map.indices_row_major() {
+            let colour = if route.contains(&(r, c)) {
+                [0, 128, 255]
+            } else {
+                heatmap_colour(*self.height_map.get(r, c).unwrap() as f64 / 25.0)
+            };
+            colours.set(r, c, colour).unwrap();
+        }
+        write_2d_array_as_ppm(&colours, path, |colour| *colour)
+    }
 }
 
 fn main() {
@@ -184,4 +334,50 @@ fn main() {
     let length_of_shortest_path_from_lowest_points_to_finish =
         valley.find_length_of_shortest_path_from_lowest_points_to_finish();
     println!("The length of the shortest path from all lowest points to finish is {length_of_shortest_path_from_lowest_points_to_finish}");
+
+    let route_from_start = valley.find_shortest_route_from_start_to_finish().unwrap();
+    println!(
+        "The shortest route from start to finish takes {} steps:",
+        route_from_start.len() - 1
+    );
+    print!("{}", valley.render_route(&route_from_start));
+
+    let route_from_lowest_points = valley
+        .find_shortest_route_from_lowest_points_to_finish()
+        .unwrap();
+    let (start_row, start_column) = route_from_lowest_points[0];
+    println!(
+        "The shortest route from the lowest points starts at row {start_row} column {start_column}"
+    );
+    valley
+        .export_route_as_ppm(&route_from_lowest_points, "day12_route.ppm")
+        .unwrap();
+    println!("The shortest route from the lowest points was written to day12_route.ppm");
+
+    let diagonal_valley = Valley::from_str(&input)
+        .unwrap()
+        .with_rules(MaxAscent(1), Movement::OrthogonalAndDiagonal);
+    let length_of_shortest_diagonal_path =
+        diagonal_valley.find_length_of_shortest_path_from_start_to_finish();
+    println!("The length of the shortest path from start to finish when moving diagonally too is {length_of_shortest_diagonal_path}");
+
+    let careful_valley = Valley::from_str(&input)
+        .unwrap()
+        .with_rules(MaxDescent(1), Movement::Orthogonal);
+    let length_of_shortest_careful_path =
+        careful_valley.find_length_of_shortest_path_from_start_to_finish();
+    println!("The length of the shortest path from start to finish when descending at most 1 at a time is {length_of_shortest_careful_path}");
+
+    let effortful_valley = Valley::from_str(&input)
+        .unwrap()
+        .with_rules(HeightChangeCost { cost_per_unit: 5 }, Movement::Orthogonal);
+    let cost_of_cheapest_effortful_path =
+        effortful_valley.find_length_of_shortest_path_from_start_to_finish();
+    let route_of_cheapest_effortful_path = effortful_valley
+        .find_shortest_route_from_start_to_finish()
+        .unwrap();
+    println!(
+        "The cheapest path from start to finish when height changes cost 5 each costs {cost_of_cheapest_effortful_path} and takes {} steps",
+        route_of_cheapest_effortful_path.len() - 1
+    );
 }
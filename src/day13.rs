@@ -1,113 +1,264 @@
-use advent_of_code_2022::{
-    read_file_to_string, unwrap_enum_variant_inner, EMPTY_LINE_PATTERN, LINE_SEPARATOR,
+use advent_of_code_2022::{read_file_to_string, EMPTY_LINE_PATTERN, LINE_SEPARATOR};
+use itertools::Itertools;
+use serde_json::Value;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    fs::write,
+    slice,
+    str::FromStr,
 };
-use std::{cmp::Ordering, str::FromStr};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Packet {
-    Integer(u8),
+    Integer(u64),
     List(Vec<Packet>),
 }
 
-fn traverse_syntax_tree<'a>(root: &'a mut Vec<Packet>, indices: &[usize]) -> &'a mut Vec<Packet> {
-    let mut elem = root;
-    for &i in indices {
-        elem = unwrap_enum_variant_inner!(&mut elem[i], Packet::List);
+// recursive descent parser, where errors mention the position they were found at
+struct PacketParser<'a> {
+    s: &'a str,
+    position: usize,
+}
+
+impl PacketParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.position..].chars().next()
+    }
+
+    fn error(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(c) => format!(
+                "Expected {expected} at position {}, found '{c}'",
+                self.position
+            ),
+            None => format!(
+                "Expected {expected} at position {}, found the end of the packet",
+                self.position
+            ),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{c}'")))
+        }
+    }
+
+    fn parse_packet(&mut self) -> Result<Packet, String> {
+        match self.peek() {
+            Some('[') => self.parse_list(),
+            Some(c) if c.is_ascii_digit() => self.parse_integer(),
+            _ => Err(self.error("'[' or an integer")),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Packet, String> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Packet::List(elements));
+        }
+        loop {
+            elements.push(self.parse_packet()?);
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(Packet::List(elements));
+                }
+                _ => return Err(self.error("',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_integer(&mut self) -> Result<Packet, String> {
+        let start = self.position;
+        let len = self.s[start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.s.len() - start);
+        self.position += len;
+        self.s[start..self.position]
+            .parse()
+            .map(Packet::Integer)
+            .map_err(|_| format!("Integer at position {start} is too large"))
     }
-    elem
 }
 
 impl FromStr for Packet {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut root = Self::List(Vec::new());
-        let root_vec = unwrap_enum_variant_inner!(&mut root, Self::List);
-        let mut depth_stack = Vec::new();
-        let mut iter = s.chars().peekable();
-        iter.next();
-        while let Some(c) = iter.next() {
-            let current_vec = traverse_syntax_tree(root_vec, &depth_stack);
-            if c.is_digit(10) {
-                if c == '1' {
-                    if let Some(&next) = iter.peek() {
-                        if next == '0' {
-                            current_vec.push(Self::Integer(10));
-                            iter.next();
-                        } else {
-                            current_vec.push(Self::Integer(1));
-                        }
-                    } else {
-                        current_vec.push(Self::Integer(1));
-                    }
-                } else {
-                    let digit = c.to_digit(10).unwrap() as u8;
-                    current_vec.push(Self::Integer(digit));
-                }
-            } else if c == '[' {
-                current_vec.push(Self::List(Vec::new()));
-                depth_stack.push(current_vec.len() - 1);
-            } else if c == ']' {
-                depth_stack.pop();
-            } else if c == ',' {
-                // do nothing
-            } else {
-                return Err("Invalid format".to_string());
-            }
+        let mut parser = PacketParser { s, position: 0 };
+        if parser.peek() != Some('[') {
+            return Err(parser.error("'['"));
         }
+        let packet = parser.parse_packet()?;
+        if parser.position < s.len() {
+            return Err(parser.error("the end of the packet"));
+        }
+        Ok(packet)
+    }
+}
 
-        Ok(root)
+impl Display for Packet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Integer(n) => write!(f, "{n}"),
+            Self::List(elements) => write!(f, "[{}]", elements.iter().join(",")),
+        }
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum CompareResult {
-    RightOrder,
-    WrongOrder,
-    Continue,
+/*
+ * lists are compared element by element, with the shorter list coming first if they agree,
+ * and an integer compared with a list is treated as a list holding only that integer;
+ * this means packets like `[[2]]` and `2` are equal, even though they are written differently
+ */
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Integer(lhs), Self::Integer(rhs)) => lhs.cmp(rhs),
+            (Self::List(lhs), Self::List(rhs)) => lhs.cmp(rhs),
+            (Self::Integer(_), Self::List(rhs)) => slice::from_ref(self).cmp(rhs),
+            (Self::List(lhs), Self::Integer(_)) => lhs.as_slice().cmp(slice::from_ref(other)),
+        }
+    }
 }
 
-fn compare_packets(lhs: &Packet, rhs: &Packet) -> CompareResult {
-    match (lhs, rhs) {
-        (Packet::Integer(lhs), Packet::Integer(rhs)) => {
-            if lhs < rhs {
-                CompareResult::RightOrder
-            } else if lhs > rhs {
-                CompareResult::WrongOrder
-            } else {
-                CompareResult::Continue
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Packet {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Packet {}
+
+// packets are JSON arrays of non-negative integers
+impl From<&Packet> for Value {
+    fn from(packet: &Packet) -> Self {
+        match packet {
+            Packet::Integer(n) => Value::from(*n),
+            Packet::List(elements) => Value::Array(elements.iter().map(Value::from).collect()),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Packet {
+    type Error = String;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        // the path uses the notation of JSONPath, e.g. `$[1][0]`
+        fn convert(value: &Value, path: &str) -> Result<Packet, String> {
+            match value {
+                Value::Array(elements) => elements
+                    .iter()
+                    .enumerate()
+                    .map(|(i, element)| convert(element, &format!("{path}[{i}]")))
+                    .collect::<Result<_, _>>()
+                    .map(Packet::List),
+                Value::Number(n) => n.as_u64().map(Packet::Integer).ok_or(format!(
+                    "Expected a non-negative integer at {path}, found {n}"
+                )),
+                value => Err(format!(
+                    "Expected an array or an integer at {path}, found {value}"
+                )),
             }
         }
-        (Packet::List(lhs), Packet::List(rhs)) => {
-            let shorter_len = lhs.len().min(rhs.len());
-            for i in 0..shorter_len {
-                let result = compare_packets(&lhs[i], &rhs[i]);
-                if result != CompareResult::Continue {
-                    return result;
+
+        if !value.is_array() {
+            return Err(format!("Expected an array at $, found {value}"));
+        }
+        convert(value, "$")
+    }
+}
+
+/*
+ * compares the packets like `cmp` does, while writing down each step the way the puzzle
+ * explains its examples, with nested comparisons indented under the one they are part of
+ */
+fn explain_comparison_at_depth(
+    lhs: &Packet,
+    rhs: &Packet,
+    depth: usize,
+    output: &mut String,
+) -> Ordering {
+    let indent = "  ".repeat(depth);
+    output.push_str(&format!("{indent}- Compare {lhs} vs {rhs}\n"));
+    let conclude = |output: &mut String, reason: &str, ordering: Ordering| {
+        let verdict = if ordering == Ordering::Less {
+            "in the right order"
+        } else {
+            "not in the right order"
+        };
+        output.push_str(&format!("{indent}  - {reason}, so inputs are {verdict}\n"));
+        ordering
+    };
+
+    match (lhs, rhs) {
+        (Packet::Integer(l), Packet::Integer(r)) => match l.cmp(r) {
+            Ordering::Less => conclude(output, "Left side is smaller", Ordering::Less),
+            Ordering::Greater => conclude(output, "Right side is smaller", Ordering::Greater),
+            Ordering::Equal => Ordering::Equal,
+        },
+        (Packet::List(l), Packet::List(r)) => {
+            for (l, r) in l.iter().zip(r) {
+                let ordering = explain_comparison_at_depth(l, r, depth + 1, output);
+                if ordering != Ordering::Equal {
+                    return ordering;
                 }
             }
-            if lhs.len() < rhs.len() {
-                CompareResult::RightOrder
-            } else if lhs.len() > rhs.len() {
-                CompareResult::WrongOrder
-            } else {
-                CompareResult::Continue
+            match l.len().cmp(&r.len()) {
+                Ordering::Less => conclude(output, "Left side ran out of items", Ordering::Less),
+                Ordering::Greater => {
+                    conclude(output, "Right side ran out of items", Ordering::Greater)
+                }
+                Ordering::Equal => Ordering::Equal,
             }
         }
-        (lhs, rhs) => {
-            if let Packet::Integer(x) = lhs {
-                let lhs = Packet::List(vec![Packet::Integer(*x)]);
-                compare_packets(&lhs, rhs)
-            } else if let Packet::Integer(x) = rhs {
-                let rhs = Packet::List(vec![Packet::Integer(*x)]);
-                compare_packets(lhs, &rhs)
-            } else {
-                unreachable!("The first 2 cases should cover everything")
-            }
+        (Packet::Integer(_), Packet::List(_)) => {
+            let lhs = Packet::List(vec![lhs.clone()]);
+            output.push_str(&format!(
+                "{indent}  - Mixed types; convert left to {lhs} and retry comparison\n"
+            ));
+            explain_comparison_at_depth(&lhs, rhs, depth + 1, output)
+        }
+        (Packet::List(_), Packet::Integer(_)) => {
+            let rhs = Packet::List(vec![rhs.clone()]);
+            output.push_str(&format!(
+                "{indent}  - Mixed types; convert right to {rhs} and retry comparison\n"
+            ));
+            explain_comparison_at_depth(lhs, &rhs, depth + 1, output)
         }
     }
 }
 
+fn explain_comparison(lhs: &Packet, rhs: &Packet) -> String {
+    let mut output = String::new();
+    if explain_comparison_at_depth(lhs, rhs, 0, &mut output) == Ordering::Equal {
+        output.push_str("- Inputs are equal, so their order cannot be decided\n");
+    }
+    output
+}
+
+fn explain_all_comparisons(pairs: &[(Packet, Packet)]) -> String {
+    pairs
+        .iter()
+        .enumerate()
+        .map(|(i, (lhs, rhs))| format!("== Pair {} ==\n{}", i + 1, explain_comparison(lhs, rhs)))
+        .join("\n")
+}
+
 fn parse_packet_pairs(s: &str) -> Vec<(Packet, Packet)> {
     let split = s.split(EMPTY_LINE_PATTERN);
     split
@@ -125,13 +276,7 @@ fn calculate_sum_of_indices_of_correct_pairs(pairs: &[(Packet, Packet)]) -> usiz
     pairs
         .iter()
         .enumerate()
-        .filter_map(|(i, (lhs, rhs))| {
-            if compare_packets(lhs, rhs) == CompareResult::RightOrder {
-                Some(i + 1)
-            } else {
-                None
-            }
-        })
+        .filter_map(|(i, (lhs, rhs))| if lhs < rhs { Some(i + 1) } else { None })
         .sum()
 }
 
@@ -142,42 +287,40 @@ fn parse_all_packets(s: &str) -> Vec<Packet> {
         .collect()
 }
 
-fn sort_packets_and_calculate_decoder_key(mut packets: Vec<Packet>) -> usize {
-    packets.sort_by(|a, b| {
-        let result = compare_packets(a, b);
-        match result {
-            CompareResult::RightOrder => Ordering::Less,
-            CompareResult::WrongOrder => Ordering::Greater,
-            CompareResult::Continue => Ordering::Equal,
-        }
-    });
-    packets
-        .iter()
-        .enumerate()
-        .filter_map(|(i, p)| {
-            let Packet::List(outer_list) = p else {
-                return None;
-            };
-            if outer_list.len() != 1 {
-                return None;
-            }
+struct DecoderKey {
+    key: usize,
+    // the 1-based index of each divider packet after sorting, in the order they were given
+    divider_indices: Vec<usize>,
+}
 
-            let Packet::List(inner_list) = &outer_list[0] else {
-                return None;
-            };
-            if inner_list.len() != 1 {
-                return None;
-            }
+/*
+ * the dividers are sorted along with the packets, but are tracked separately, as a divider can
+ * be equal to other packets; equal packets keep their relative order, with dividers coming last
+ */
+fn sort_packets_and_calculate_decoder_key(packets: Vec<Packet>, dividers: &[Packet]) -> DecoderKey {
+    let mut tagged_packets = packets
+        .into_iter()
+        .map(|p| (p, None))
+        .chain(
+            dividers
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(i, p)| (p, Some(i))),
+        )
+        .collect::<Vec<_>>();
+    tagged_packets.sort_by(|(a, _), (b, _)| a.cmp(b));
 
-            let Packet::Integer(n) = inner_list[0] else {
-                return None;
-            };
-            if n == 2 || n == 6 {
-                return Some(i + 1);
-            }
-            None
-        })
-        .product()
+    let mut divider_indices = vec![0; dividers.len()];
+    for (i, (_, divider)) in tagged_packets.iter().enumerate() {
+        if let Some(divider) = divider {
+            divider_indices[*divider] = i + 1;
+        }
+    }
+    DecoderKey {
+        key: divider_indices.iter().product(),
+        divider_indices,
+    }
 }
 
 fn main() {
@@ -186,9 +329,64 @@ fn main() {
     let sum_of_indices_of_correct_pairs = calculate_sum_of_indices_of_correct_pairs(&packet_pairs);
     println!("The sum of the indices of correct packet pairs is {sum_of_indices_of_correct_pairs}");
 
-    let mut all_packets = parse_all_packets(&input);
-    all_packets.push(Packet::List(vec![Packet::List(vec![Packet::Integer(2)])]));
-    all_packets.push(Packet::List(vec![Packet::List(vec![Packet::Integer(6)])]));
-    let decoder_key = sort_packets_and_calculate_decoder_key(all_packets);
+    if let Some((i, (lhs, rhs))) = packet_pairs
+        .iter()
+        .enumerate()
+        .find(|(_, (lhs, rhs))| lhs > rhs)
+    {
+        println!(
+            "Pair {} is the first one in the wrong order, because:",
+            i + 1
+        );
+        print!("{}", explain_comparison(lhs, rhs));
+    }
+    write(
+        "day13_explanations.txt",
+        explain_all_comparisons(&packet_pairs),
+    )
+    .unwrap();
+    println!("The explanations of all comparisons were written to day13_explanations.txt");
+
+    let all_packets = parse_all_packets(&input);
+    let dividers = [
+        Packet::List(vec![Packet::List(vec![Packet::Integer(2)])]),
+        Packet::List(vec![Packet::List(vec![Packet::Integer(6)])]),
+    ];
+    let decoder_key = sort_packets_and_calculate_decoder_key(all_packets.clone(), &dividers).key;
     println!("The decoder key is {decoder_key}");
+
+    let custom_dividers_json =
+        serde_json::from_str::<Value>("[[[2]], [[6]], [[10]], [1, [3]]]").unwrap();
+    let custom_dividers = custom_dividers_json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| Packet::try_from(v).unwrap())
+        .collect::<Vec<_>>();
+    let custom_decoder_key =
+        sort_packets_and_calculate_decoder_key(all_packets.clone(), &custom_dividers);
+    for (divider, index) in custom_dividers
+        .iter()
+        .zip(&custom_decoder_key.divider_indices)
+    {
+        println!("The divider packet {divider} ends up at index {index}");
+    }
+    println!(
+        "The decoder key with these dividers is {}",
+        custom_decoder_key.key
+    );
+
+    let packets_round_trip_through_json = all_packets.iter().all(|p| {
+        let json = Value::from(p);
+        let text = p.to_string();
+        serde_json::to_string(&json).unwrap() == text
+            && Packet::try_from(&json).unwrap().to_string() == text
+    });
+    println!("Every packet converts to and from JSON unchanged: {packets_round_trip_through_json}");
+
+    let packets_round_trip = input
+        .lines()
+        .filter(|l| !l.is_empty())
+        .all(|l| Packet::from_str(l).unwrap().to_string() == l);
+    println!("Every packet is displayed the same way as it was written: {packets_round_trip}");
 }
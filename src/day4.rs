@@ -1,57 +1,260 @@
 use advent_of_code_2022::read_file_lines_as;
+use itertools::Itertools;
 use sscanf::scanf;
-use std::{ops::RangeInclusive, str::FromStr};
+use std::{collections::BTreeSet, ops::RangeInclusive, str::FromStr};
 
 fn is_range_contained_by(container: &RangeInclusive<u32>, containee: &RangeInclusive<u32>) -> bool {
     container.start() <= containee.start() && container.end() >= containee.end()
 }
 
+fn do_ranges_overlap(lhs: &RangeInclusive<u32>, rhs: &RangeInclusive<u32>) -> bool {
+    lhs.start() <= rhs.end() && rhs.start() <= lhs.end()
+}
+
+fn parse_range(s: &str) -> Result<RangeInclusive<u32>, String> {
+    let (from, to): (u32, u32) =
+        scanf!(s.trim(), "{u32}-{u32}").map_err(|_| format!("Invalid range format: '{s}'"))?;
+    if from > to {
+        return Err(format!("Reversed range: '{s}', start is greater than end"));
+    }
+
+    Ok(from..=to)
+}
+
 struct SectionAssignment {
-    elf_1: RangeInclusive<u32>,
-    elf_2: RangeInclusive<u32>,
+    elves: Vec<RangeInclusive<u32>>,
 }
 
 impl FromStr for SectionAssignment {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (from_1, to_1, from_2, to_2): (u32, u32, u32, u32) =
-            scanf!(s, "{u32}-{u32},{u32}-{u32}").map_err(|_| "Invalid format".to_string())?;
+        let elves = s
+            .split(',')
+            .map(parse_range)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid assignment '{s}': {e}"))?;
 
-        Ok(Self {
-            elf_1: from_1..=to_1,
-            elf_2: from_2..=to_2,
-        })
+        Ok(Self { elves })
     }
 }
 
 impl SectionAssignment {
-    fn does_one_fully_contain_the_other(&self) -> bool {
-        is_range_contained_by(&self.elf_1, &self.elf_2)
-            || is_range_contained_by(&self.elf_2, &self.elf_1)
+    fn does_one_fully_contain_another(&self) -> bool {
+        self.elves.iter().enumerate().any(|(i, container)| {
+            self.elves
+                .iter()
+                .enumerate()
+                .any(|(j, containee)| i != j && is_range_contained_by(container, containee))
+        })
+    }
+
+    fn do_all_ranges_overlap(&self) -> bool {
+        let latest_start = self.elves.iter().map(|r| *r.start()).max();
+        let earliest_end = self.elves.iter().map(|r| *r.end()).min();
+        latest_start <= earliest_end
     }
 
-    fn do_ranges_overlap(&self) -> bool {
-        self.elf_1.start() <= self.elf_2.start() && self.elf_2.start() <= self.elf_1.end()
-            || self.elf_2.start() <= self.elf_1.start() && self.elf_1.start() <= self.elf_2.end()
+    fn count_overlapping_pairs(&self) -> usize {
+        self.elves
+            .iter()
+            .tuple_combinations()
+            .filter(|(lhs, rhs)| do_ranges_overlap(lhs, rhs))
+            .count()
     }
 }
 
 fn count_filtered_assignments(
-    assignments: &Vec<SectionAssignment>,
+    assignments: &[SectionAssignment],
     f: fn(&SectionAssignment) -> bool,
 ) -> u32 {
     assignments.iter().filter(|a| f(a)).count() as u32
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct ElfId {
+    assignment_idx: usize,
+    elf_idx: usize,
+}
+
+#[derive(Debug)]
+struct Elf {
+    id: ElfId,
+    sections: RangeInclusive<u32>,
+}
+
+fn collect_elves(assignments: &[SectionAssignment]) -> Vec<Elf> {
+    assignments
+        .iter()
+        .enumerate()
+        .flat_map(|(assignment_idx, a)| {
+            a.elves
+                .iter()
+                .enumerate()
+                .map(move |(elf_idx, sections)| Elf {
+                    id: ElfId {
+                        assignment_idx,
+                        elf_idx,
+                    },
+                    sections: sections.clone(),
+                })
+        })
+        .collect()
+}
+
+/*
+ * sweeps through the elves ordered by their first section, keeping the ones whose range is
+ * still open ordered by their last section, so the ones that ended before the current elf
+ * starts can be dropped from the front; every elf still active overlaps the current one
+ */
+fn find_overlapping_elf_pairs(elves: &[Elf]) -> Vec<(ElfId, ElfId)> {
+    let mut active = BTreeSet::<(u32, ElfId)>::new();
+    let mut pairs = Vec::new();
+    for elf in elves.iter().sorted_by_key(|e| *e.sections.start()) {
+        while let Some(&(end, _)) = active.first() {
+            if end >= *elf.sections.start() {
+                break;
+            }
+            active.pop_first();
+        }
+        pairs.extend(active.iter().map(|(_, id)| (*id, elf.id)));
+        active.insert((*elf.sections.end(), elf.id));
+    }
+    pairs
+}
+
+struct SectionCoverage {
+    // consecutive, non-overlapping runs of sections with the number of elves covering them,
+    // starting from section `1` until the last section assigned to any elf;
+    // the sweep works in `u64`, so it covers the full `u32` range of sections
+    segments: Vec<(RangeInclusive<u32>, usize)>,
+}
+
+impl SectionCoverage {
+    fn new(elves: &[Elf]) -> Self {
+        let mut events = elves
+            .iter()
+            .flat_map(|e| {
+                [
+                    (*e.sections.start() as u64, 1),
+                    (*e.sections.end() as u64 + 1, -1),
+                ]
+            })
+            .collect::<Vec<(u64, i32)>>();
+        events.push((1, 0));
+        events.sort_unstable();
+
+        let mut segments = Vec::new();
+        let mut count = 0;
+        for (i, (section, delta)) in events.iter().enumerate() {
+            count += delta;
+            if let Some((next_section, _)) = events.get(i + 1) {
+                if next_section > section {
+                    // only the last event can be past `u32::MAX`, and it starts no segment
+                    let sections = *section as u32..=(next_section - 1) as u32;
+                    segments.push((sections, count as usize));
+                }
+            }
+        }
+
+        Self { segments }
+    }
+
+    fn get_coverage_of(&self, section: u32) -> usize {
+        let idx = self
+            .segments
+            .partition_point(|(sections, _)| *sections.end() < section);
+        self.segments
+            .get(idx)
+            .filter(|(sections, _)| sections.contains(&section))
+            .map_or(0, |(_, count)| *count)
+    }
+
+    fn get_maximum_coverage(&self) -> (usize, Vec<RangeInclusive<u32>>) {
+        let max = self
+            .segments
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0);
+        let sections = self
+            .segments
+            .iter()
+            .filter(|(_, count)| *count == max)
+            .map(|(sections, _)| sections.clone())
+            .collect();
+        (max, sections)
+    }
+
+    fn get_uncovered_sections(&self) -> Vec<RangeInclusive<u32>> {
+        self.segments
+            .iter()
+            .filter(|(_, count)| *count == 0)
+            .map(|(sections, _)| sections.clone())
+            .collect()
+    }
+
+    /*
+     * an elf is redundant if every section it covers is also covered by at least one other elf,
+     * meaning that none of its sections is covered by only a single elf
+     */
+    fn find_redundant_elves(&self, elves: &[Elf]) -> Vec<ElfId> {
+        let singly_covered = self
+            .segments
+            .iter()
+            .filter(|(_, count)| *count == 1)
+            .map(|(sections, _)| sections)
+            .collect::<Vec<_>>();
+
+        elves
+            .iter()
+            .filter(|e| {
+                let idx = singly_covered.partition_point(|s| s.end() < e.sections.start());
+                match singly_covered.get(idx) {
+                    Some(s) => s.start() > e.sections.end(),
+                    None => true,
+                }
+            })
+            .map(|e| e.id)
+            .collect()
+    }
+}
+
 fn main() {
     let assignments = read_file_lines_as("input/day4.txt", |l| {
         SectionAssignment::from_str(l).unwrap()
     });
     let number_of_assignments_where_one_is_fully_contained =
-        count_filtered_assignments(&assignments, |a| a.does_one_fully_contain_the_other());
-    println!("The number of assignments where one section fully contains the other is {number_of_assignments_where_one_is_fully_contained}");
+        count_filtered_assignments(&assignments, |a| a.does_one_fully_contain_another());
+    println!("The number of assignments where one section fully contains another is {number_of_assignments_where_one_is_fully_contained}");
     let number_of_assignments_where_there_is_overlap =
-        count_filtered_assignments(&assignments, |a| a.do_ranges_overlap());
-    println!("The number of assignments where one section overlaps the other is {number_of_assignments_where_there_is_overlap}");
+        count_filtered_assignments(&assignments, |a| a.do_all_ranges_overlap());
+    println!("The number of assignments where all sections overlap is {number_of_assignments_where_there_is_overlap}");
+    let number_of_overlapping_pairs_within_assignments = assignments
+        .iter()
+        .map(|a| a.count_overlapping_pairs())
+        .sum::<usize>();
+    println!("The number of overlapping pairs within assignments is {number_of_overlapping_pairs_within_assignments}");
+
+    let elves = collect_elves(&assignments);
+    let overlapping_elf_pairs = find_overlapping_elf_pairs(&elves);
+    println!(
+        "The number of elf pairs across all assignments whose sections overlap is {}",
+        overlapping_elf_pairs.len()
+    );
+
+    let coverage = SectionCoverage::new(&elves);
+    let (maximum_coverage, most_covered_sections) = coverage.get_maximum_coverage();
+    println!("The maximum number of elves covering the same section is {maximum_coverage}, reached in sections {most_covered_sections:?}");
+    println!(
+        "The first section is covered by {} elves",
+        coverage.get_coverage_of(1)
+    );
+    let uncovered_sections = coverage.get_uncovered_sections();
+    println!("The sections not covered by any elf are {uncovered_sections:?}");
+    let redundant_elves = coverage.find_redundant_elves(&elves);
+    println!(
+        "The number of elves whose sections are all covered by others is {}",
+        redundant_elves.len()
+    );
 }
@@ -1,6 +1,11 @@
 use advent_of_code_2022::{read_file_to_string, EMPTY_LINE_PATTERN};
+use itertools::Itertools;
 use sscanf::scanf;
-use std::str::FromStr;
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+    str::FromStr,
+};
 
 #[derive(Debug)]
 struct Step {
@@ -21,59 +26,284 @@ impl FromStr for Step {
     }
 }
 
-fn get_char_idx(stack_idx: usize) -> usize {
-    1 + 4 * stack_idx
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.quantity, self.from, self.to
+        )
+    }
+}
+
+trait Crane {
+    fn name(&self) -> String;
+
+    // returns the order in which the crates lifted from the source stack (listed from bottom to top)
+    // end up on the target stack, also listed from bottom to top
+    fn arrange(&self, lifted: &[char]) -> Vec<char>;
+}
+
+// moves crates one at a time, reversing their order
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_string()
+    }
+
+    fn arrange(&self, lifted: &[char]) -> Vec<char> {
+        lifted.iter().rev().copied().collect()
+    }
+}
+
+// moves all crates at once, retaining their order
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_string()
+    }
+
+    fn arrange(&self, lifted: &[char]) -> Vec<char> {
+        lifted.to_vec()
+    }
+}
+
+// moves at most `capacity` crates at once, starting from the top, retaining the order inside each lift
+struct LimitedCapacityCrane {
+    capacity: usize,
+}
+
+impl LimitedCapacityCrane {
+    fn new(capacity: usize) -> Result<Self, String> {
+        if capacity == 0 {
+            return Err("A crane must be able to lift at least 1 crate".to_string());
+        }
+        Ok(Self { capacity })
+    }
+}
+
+impl Crane for LimitedCapacityCrane {
+    fn name(&self) -> String {
+        format!("limited crane (capacity {})", self.capacity)
+    }
+
+    fn arrange(&self, lifted: &[char]) -> Vec<char> {
+        lifted
+            .rchunks(self.capacity)
+            .flat_map(|lift| lift.iter().copied())
+            .collect()
+    }
+}
+
+// returns the character span of each stack label, e.g. ` 1   2 ... 10  11` gives `[1..2, 5..6, ..., 37..39, 41..43]`
+fn parse_label_spans(label_line: &str) -> Result<Vec<Range<usize>>, String> {
+    let chars = label_line.chars().collect::<Vec<_>>();
+    let mut spans = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        if chars[idx].is_whitespace() {
+            idx += 1;
+            continue;
+        }
+        let start = idx;
+        while idx < chars.len() && !chars[idx].is_whitespace() {
+            idx += 1;
+        }
+        let label = chars[start..idx].iter().collect::<String>();
+        let expected = spans.len() + 1;
+        if label.parse::<usize>() != Ok(expected) {
+            return Err(format!(
+                "Invalid stack label '{label}' at column {start}, expected '{expected}'"
+            ));
+        }
+        spans.push(start..idx);
+    }
+
+    if spans.is_empty() {
+        return Err("The label line does not contain any stacks".to_string());
+    }
+
+    Ok(spans)
 }
 
 #[derive(Debug)]
-struct CrateMover {
+struct ExecutedStep {
+    from: usize,
+    to: usize,
+    // crates taken from the source stack, from bottom to top
+    lifted: Vec<char>,
+    // crates put onto the target stack, from bottom to top
+    placed: Vec<char>,
+}
+
+#[derive(Debug)]
+struct CrateArea {
     stacks: Vec<Vec<char>>,
+    history: Vec<ExecutedStep>,
+    undone: Vec<ExecutedStep>,
 }
 
-impl FromStr for CrateMover {
+impl FromStr for CrateArea {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut iter = s.lines().rev();
-        let number_line = iter.next().ok_or("Invalid format".to_string())?.trim_end();
-        let num_of_stacks = number_line
-            .chars()
-            .last()
-            .ok_or("Invalid format".to_string())?
-            .to_digit(10)
-            .ok_or("Invalid format".to_string())? as usize;
-
-        let mut stacks = vec![Vec::new(); num_of_stacks];
-
-        for line in iter {
-            for i in 0..num_of_stacks {
-                let idx = get_char_idx(i);
-                if let Some(item) = line.chars().nth(idx) {
-                    if item != ' ' {
-                        stacks[i].push(item);
-                    }
+        let label_line = iter.next().ok_or("Missing stack label line".to_string())?;
+        let label_spans = parse_label_spans(label_line)?;
+
+        let mut stacks = vec![Vec::new(); label_spans.len()];
+
+        for (level, line) in iter.enumerate() {
+            let chars = line.chars().collect::<Vec<_>>();
+            let mut idx = 0;
+            while idx < chars.len() {
+                if chars[idx].is_whitespace() {
+                    idx += 1;
+                    continue;
                 }
+                let (Some('['), Some(&item), Some(']')) =
+                    (chars.get(idx), chars.get(idx + 1), chars.get(idx + 2))
+                else {
+                    return Err(format!("Invalid crate at column {idx} of line '{line}'"));
+                };
+
+                // the label that overlaps the brackets of the crate determines its stack
+                let crate_span = idx..(idx + 3);
+                let mut matching_stacks = label_spans.iter().positions(|label_span| {
+                    label_span.start < crate_span.end && crate_span.start < label_span.end
+                });
+                let (Some(stack_idx), None) = (matching_stacks.next(), matching_stacks.next())
+                else {
+                    return Err(format!(
+                        "Crate at column {idx} of line '{line}' is not aligned with a single stack label"
+                    ));
+                };
+                if stacks[stack_idx].len() != level {
+                    return Err(format!(
+                        "Crate at column {idx} of line '{line}' is floating above stack {}",
+                        stack_idx + 1
+                    ));
+                }
+                stacks[stack_idx].push(item);
+
+                idx += 3;
             }
         }
 
-        Ok(Self { stacks })
+        Ok(Self {
+            stacks,
+            history: Vec::new(),
+            undone: Vec::new(),
+        })
     }
 }
 
-impl CrateMover {
-    fn execute_9000(&mut self, step: &Step) {
-        for _ in 0..step.quantity {
-            let Some(top) = self.stacks[step.from - 1].pop() else {
-                break;
-            };
-            self.stacks[step.to - 1].push(top);
+impl Display for CrateArea {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let labels = (1..=self.stacks.len())
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>();
+        let widths = labels.iter().map(|l| l.len().max(3)).collect::<Vec<_>>();
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+
+        for level in (0..height).rev() {
+            let row = self
+                .stacks
+                .iter()
+                .zip(&widths)
+                .map(|(stack, &width)| match stack.get(level) {
+                    Some(item) => format!("{:^width$}", format!("[{item}]")),
+                    None => " ".repeat(width),
+                })
+                .join(" ");
+            writeln!(f, "{}", row.trim_end())?;
+        }
+
+        let label_row = labels
+            .iter()
+            .zip(&widths)
+            .map(|(label, &width)| format!("{label:^width$}"))
+            .join(" ");
+        write!(f, "{}", label_row.trim_end())
+    }
+}
+
+impl CrateArea {
+    fn get_stack_idx(&self, stack: usize) -> Result<usize, String> {
+        if (1..=self.stacks.len()).contains(&stack) {
+            Ok(stack - 1)
+        } else {
+            Err(format!(
+                "stack {stack} does not exist, there are {} stacks",
+                self.stacks.len()
+            ))
+        }
+    }
+
+    fn execute(&mut self, crane: &dyn Crane, step: &Step) -> Result<(), String> {
+        let from = self.get_stack_idx(step.from)?;
+        let to = self.get_stack_idx(step.to)?;
+        if from == to {
+            return Err(format!(
+                "cannot move crates from stack {} onto itself",
+                step.from
+            ));
+        }
+        let available = self.stacks[from].len();
+        if step.quantity > available {
+            return Err(format!(
+                "cannot move {} crates from stack {} as it only has {available}",
+                step.quantity, step.from
+            ));
+        }
+
+        let lifted = self.stacks[from]
+            .drain((available - step.quantity)..)
+            .collect::<Vec<_>>();
+        let placed = crane.arrange(&lifted);
+        self.stacks[to].extend(&placed);
+
+        self.history.push(ExecutedStep {
+            from,
+            to,
+            lifted,
+            placed,
+        });
+        self.undone.clear();
+
+        Ok(())
+    }
+
+    fn execute_all(&mut self, crane: &dyn Crane, steps: &[Step]) -> Result<(), String> {
+        for (i, step) in steps.iter().enumerate() {
+            self.execute(crane, step)
+                .map_err(|e| format!("Step {} '{step}' is invalid: {e}", i + 1))?;
         }
+        Ok(())
+    }
+
+    fn undo(&mut self) -> bool {
+        let Some(executed) = self.history.pop() else {
+            return false;
+        };
+        let n = self.stacks[executed.to].len() - executed.placed.len();
+        self.stacks[executed.to].truncate(n);
+        self.stacks[executed.from].extend(&executed.lifted);
+        self.undone.push(executed);
+        true
     }
 
-    fn execute_9001(&mut self, step: &Step) {
-        let n = self.stacks[step.from - 1].len() - step.quantity;
-        let items_to_move = self.stacks[step.from - 1].drain(n..).collect::<Vec<_>>();
-        self.stacks[step.to - 1].extend(items_to_move);
+    fn redo(&mut self) -> bool {
+        let Some(executed) = self.undone.pop() else {
+            return false;
+        };
+        let n = self.stacks[executed.from].len() - executed.lifted.len();
+        self.stacks[executed.from].truncate(n);
+        self.stacks[executed.to].extend(&executed.placed);
+        self.history.push(executed);
+        true
     }
 
     fn get_top_crates(&self) -> String {
@@ -84,30 +314,43 @@ impl CrateMover {
     }
 }
 
-fn get_top_row_after_rearranging_crates_using_crate_mover_9000_rules(
+fn get_top_row_after_rearranging_crates(
     crates: &str,
-    steps: &Vec<Step>,
-) -> String {
-    let mut crate_area = CrateMover::from_str(crates).unwrap();
+    steps: &[Step],
+    crane: &dyn Crane,
+) -> Result<String, String> {
+    let mut crate_area = CrateArea::from_str(crates)?;
+    crate_area.execute_all(crane, steps)?;
+    Ok(crate_area.get_top_crates())
+}
+
+fn print_rearrangement(crates: &str, steps: &[Step], crane: &dyn Crane) {
+    let mut crate_area = CrateArea::from_str(crates).unwrap();
+    println!("{crate_area}");
 
     for step in steps {
-        crate_area.execute_9000(step);
+        crate_area.execute(crane, step).unwrap();
+        println!();
+        println!("After {step}:");
+        println!("{crate_area}");
     }
-
-    crate_area.get_top_crates()
 }
 
-fn get_top_row_after_rearranging_crates_using_crate_mover_9001_rules(
+fn get_top_row_after_undoing_and_redoing_all_steps(
     crates: &str,
-    steps: &Vec<Step>,
-) -> String {
-    let mut crate_mover = CrateMover::from_str(crates).unwrap();
+    steps: &[Step],
+    crane: &dyn Crane,
+) -> Result<(String, String), String> {
+    let mut crate_area = CrateArea::from_str(crates)?;
+    crate_area.execute_all(crane, steps)?;
 
-    for step in steps {
-        crate_mover.execute_9001(step);
-    }
+    while crate_area.undo() {}
+    let top_row_after_undo = crate_area.get_top_crates();
 
-    crate_mover.get_top_crates()
+    while crate_area.redo() {}
+    let top_row_after_redo = crate_area.get_top_crates();
+
+    Ok((top_row_after_undo, top_row_after_redo))
 }
 
 fn main() {
@@ -119,15 +362,22 @@ fn main() {
         .map(|l| Step::from_str(l).unwrap())
         .collect::<Vec<_>>();
 
-    let top_row_crate_mover_9000 =
-        get_top_row_after_rearranging_crates_using_crate_mover_9000_rules(crates, &steps);
-    println!(
-        "The top row after rearranging with CrateMover 9000 rules is: {top_row_crate_mover_9000}"
-    );
-
-    let top_row_crate_mover_9001 =
-        get_top_row_after_rearranging_crates_using_crate_mover_9001_rules(crates, &steps);
-    println!(
-        "The top row after rearranging with CrateMover 9001 rules is: {top_row_crate_mover_9001}"
-    );
+    let limited_capacity_crane = LimitedCapacityCrane::new(3).unwrap();
+    let cranes: [&dyn Crane; 3] = [&CrateMover9000, &CrateMover9001, &limited_capacity_crane];
+    for crane in cranes {
+        let name = crane.name();
+        match get_top_row_after_rearranging_crates(crates, &steps, crane) {
+            Ok(top_row) => {
+                println!("The top row after rearranging using {name} is: {top_row}")
+            }
+            Err(e) => println!("Rearranging using {name} failed: {e}"),
+        }
+    }
+
+    let (top_row_after_undo, top_row_after_redo) =
+        get_top_row_after_undoing_and_redoing_all_steps(crates, &steps, &CrateMover9001).unwrap();
+    println!("The top row after undoing all CrateMover 9001 steps is: {top_row_after_undo}, and after redoing them is: {top_row_after_redo}");
+
+    println!("The first 3 steps of the rearrangement using CrateMover 9001 rules are:");
+    print_rearrangement(crates, &steps[..3], &CrateMover9001);
 }
@@ -1,12 +1,140 @@
 use advent_of_code_2022::read_file_to_string;
-use itertools::Itertools;
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufReader, Read},
+};
+
+// keeps track of the last `window_size` bytes and how many of them are repeats,
+// so checking whether they are all distinct takes constant time for each new byte
+struct MarkerDetector {
+    window_size: usize,
+    window: VecDeque<u8>,
+    occurrences: [usize; 256],
+    num_of_repeats: usize,
+}
+
+impl MarkerDetector {
+    fn new(window_size: usize) -> Self {
+        assert!(window_size > 0, "The window size must be at least 1");
+        Self {
+            window_size,
+            window: VecDeque::with_capacity(window_size),
+            occurrences: [0; 256],
+            num_of_repeats: 0,
+        }
+    }
+
+    // returns whether the last `window_size` bytes including `byte` form a marker
+    fn push(&mut self, byte: u8) -> bool {
+        if self.window.len() == self.window_size {
+            let oldest = self.window.pop_front().unwrap() as usize;
+            self.occurrences[oldest] -= 1;
+            if self.occurrences[oldest] > 0 {
+                self.num_of_repeats -= 1;
+            }
+        }
+
+        self.occurrences[byte as usize] += 1;
+        if self.occurrences[byte as usize] > 1 {
+            self.num_of_repeats += 1;
+        }
+        self.window.push_back(byte);
+
+        self.window.len() == self.window_size && self.num_of_repeats == 0
+    }
+
+    fn matches(&self, tolerance: &Tolerance) -> bool {
+        if self.window.len() < self.window_size {
+            return false;
+        }
+        match tolerance {
+            Tolerance::MinDistinct(k) => self.window_size - self.num_of_repeats >= *k,
+            Tolerance::MaxRepeats(d) => self.num_of_repeats <= *d,
+        }
+    }
+}
+
+// describes how corrupted a window can be while still counting as a marker
+#[derive(Debug)]
+enum Tolerance {
+    // at least this many different characters are in the window
+    MinDistinct(usize),
+    // at most this many characters in the window are repeats of an earlier one
+    MaxRepeats(usize),
+}
+
+fn find_position_of_first_window_within_tolerance(
+    s: &str,
+    n: usize,
+    tolerance: &Tolerance,
+) -> Option<usize> {
+    let mut detector = MarkerDetector::new(n);
+    s.bytes()
+        .position(|b| {
+            detector.push(b);
+            detector.matches(tolerance)
+        })
+        .map(|idx| idx + 1)
+}
 
 fn find_position_of_first_n_unique_chars(s: &str, n: usize) -> Option<usize> {
-    let v = s.chars().collect::<Vec<_>>();
-    let result = v
-        .windows(n)
-        .find_position(|x| x.iter().unique().count() == n);
-    result.map(|(idx, _)| idx + n)
+    find_position_of_first_window_within_tolerance(s, n, &Tolerance::MaxRepeats(0))
+}
+
+// returns the start index and the length of the longest run of characters that are all different
+fn find_longest_run_of_unique_chars(s: &str) -> (usize, usize) {
+    let mut last_seen_at = [None; 256];
+    let mut run_start = 0;
+    let mut longest = (0, 0);
+    for (idx, b) in s.bytes().enumerate() {
+        if let Some(previous_idx) = last_seen_at[b as usize] {
+            run_start = run_start.max(previous_idx + 1);
+        }
+        last_seen_at[b as usize] = Some(idx);
+
+        let run_len = idx + 1 - run_start;
+        if run_len > longest.1 {
+            longest = (run_start, run_len);
+        }
+    }
+    longest
+}
+
+/*
+ * reads the stream once, feeding every byte to a detector for each window size; returns the
+ * positions of the markers for each window size, either all of them or only the first one,
+ * in which case reading stops as soon as every detector has found its marker;
+ * line breaks are not part of the signal, so they are skipped
+ */
+fn find_markers(
+    reader: impl Read,
+    window_sizes: &[usize],
+    find_all: bool,
+) -> io::Result<Vec<Vec<usize>>> {
+    let mut detectors = window_sizes
+        .iter()
+        .map(|&n| MarkerDetector::new(n))
+        .collect::<Vec<_>>();
+    let mut markers = vec![Vec::new(); window_sizes.len()];
+
+    let bytes = BufReader::new(reader)
+        .bytes()
+        .filter(|b| !matches!(b, Ok(b'\n' | b'\r')));
+    for (idx, byte) in bytes.enumerate() {
+        let byte = byte?;
+        for (detector, positions) in detectors.iter_mut().zip(markers.iter_mut()) {
+            if (find_all || positions.is_empty()) && detector.push(byte) {
+                positions.push(idx + 1);
+            }
+        }
+
+        if !find_all && markers.iter().all(|positions| !positions.is_empty()) {
+            break;
+        }
+    }
+
+    Ok(markers)
 }
 
 fn main() {
@@ -19,4 +147,25 @@ fn main() {
     let position_of_first_start_of_message_marker =
         find_position_of_first_n_unique_chars(&input, 14).unwrap();
     println!("The position of the first start-of-message marker is {position_of_first_start_of_message_marker}");
+
+    let position_of_first_noisy_start_of_message_marker =
+        find_position_of_first_window_within_tolerance(&input, 14, &Tolerance::MinDistinct(12))
+            .unwrap();
+    println!("The position of the first start-of-message marker with at least 12 distinct characters is {position_of_first_noisy_start_of_message_marker}");
+
+    let position_of_first_start_of_message_marker_with_one_repeat =
+        find_position_of_first_window_within_tolerance(&input, 14, &Tolerance::MaxRepeats(1))
+            .unwrap();
+    println!("The position of the first start-of-message marker with at most 1 repeated character is {position_of_first_start_of_message_marker_with_one_repeat}");
+
+    let (longest_run_start, longest_run_len) = find_longest_run_of_unique_chars(input.trim_end());
+    println!("The longest run of distinct characters starts at {longest_run_start} and is {longest_run_len} characters long");
+
+    let file = File::open("input/day6.txt").unwrap();
+    let all_markers = find_markers(file, &[4, 14], true).unwrap();
+    println!(
+        "The stream contains {} start-of-packet markers and {} start-of-message markers",
+        all_markers[0].len(),
+        all_markers[1].len()
+    );
 }
@@ -1,5 +1,10 @@
 use advent_of_code_2022::read_file_lines_as;
-use std::{collections::HashMap, str::FromStr};
+use itertools::Itertools;
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
 
 const TOTAL_DISK_SPACE: usize = 70000000;
 const REQUIRED_FREE_SPACE: usize = 30000000;
@@ -8,20 +13,33 @@ const REQUIRED_FREE_SPACE: usize = 30000000;
 enum Command {
     Cd { path: String },
     Ls,
+    Mkdir { path: String },
+    Rm { path: String },
+    Cat { path: String },
 }
 
 impl FromStr for Command {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (_, cmd) = s.split_once(' ').ok_or("Invalid format".to_string())?;
-        if cmd == "ls" {
-            Ok(Self::Ls)
-        } else {
-            let (_, path) = cmd.split_once(' ').ok_or("Invalid format".to_string())?;
-            Ok(Self::Cd {
-                path: path.to_string(),
-            })
+        let cmd = s.strip_prefix("$ ").ok_or("Invalid format".to_string())?;
+        let mut parts = cmd.split_whitespace();
+        let name = parts.next().ok_or("Invalid format".to_string())?;
+        // flags such as `rm -r` do not change how the commands are replayed
+        let mut arguments = parts.filter(|p| !p.starts_with('-'));
+        let mut path = || {
+            arguments
+                .next()
+                .map(str::to_string)
+                .ok_or(format!("Missing path for '{name}'"))
+        };
+        match name {
+            "cd" => Ok(Self::Cd { path: path()? }),
+            "ls" => Ok(Self::Ls),
+            "mkdir" => Ok(Self::Mkdir { path: path()? }),
+            "rm" => Ok(Self::Rm { path: path()? }),
+            "cat" => Ok(Self::Cat { path: path()? }),
+            _ => Err(format!("Unknown command '{name}'")),
         }
     }
 }
@@ -29,7 +47,7 @@ impl FromStr for Command {
 #[derive(Debug)]
 enum Output {
     Dir { name: String },
-    File { size: usize },
+    File { name: String, size: usize },
 }
 
 impl FromStr for Output {
@@ -44,7 +62,7 @@ impl FromStr for Output {
             let size = size_or_dir
                 .parse()
                 .map_err(|_| "Invalid format".to_string())?;
-            Ok(Self::File { size })
+            Ok(Self::File { name, size })
         }
     }
 }
@@ -69,74 +87,779 @@ impl FromStr for TerminalLine {
     }
 }
 
-fn calculate_directory_sizes(lines: &Vec<TerminalLine>) -> HashMap<String, usize> {
-    let mut directories_with_size = HashMap::<String, usize>::new();
-    let mut current_path = Vec::<String>::new();
-    for line in lines {
+type DirectoryId = usize;
+
+const ROOT: DirectoryId = 0;
+
+#[derive(Debug)]
+struct Directory {
+    name: String,
+    parent: Option<DirectoryId>,
+    subdirectories: BTreeMap<String, DirectoryId>,
+    files: BTreeMap<String, usize>,
+    // total size of everything inside, computed on first request and cleared when the contents change
+    cached_size: Cell<Option<usize>>,
+}
+
+impl Directory {
+    fn new(name: String, parent: Option<DirectoryId>) -> Self {
+        Self {
+            name,
+            parent,
+            subdirectories: BTreeMap::new(),
+            files: BTreeMap::new(),
+            cached_size: Cell::new(None),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FileSystem {
+    directories: Vec<Directory>,
+}
+
+impl FileSystem {
+    fn new() -> Self {
+        Self {
+            directories: vec![Directory::new("/".to_string(), None)],
+        }
+    }
+
+    fn add_directory(&mut self, parent: DirectoryId, name: &str) -> DirectoryId {
+        if let Some(&id) = self.directories[parent].subdirectories.get(name) {
+            return id;
+        }
+        let id = self.directories.len();
+        self.directories
+            .push(Directory::new(name.to_string(), Some(parent)));
+        self.directories[parent]
+            .subdirectories
+            .insert(name.to_string(), id);
+        id
+    }
+
+    fn add_file(&mut self, parent: DirectoryId, name: &str, size: usize) {
+        self.directories[parent]
+            .files
+            .insert(name.to_string(), size);
+        self.invalidate_size_of(parent);
+    }
+
+    // detaches the directory from its parent, which makes it and everything inside unreachable
+    fn remove_directory(&mut self, dir: DirectoryId) {
+        let Some(parent) = self.directories[dir].parent else {
+            return;
+        };
+        let name = self.directories[dir].name.clone();
+        self.directories[parent].subdirectories.remove(&name);
+        self.invalidate_size_of(parent);
+    }
+
+    fn remove_file(&mut self, parent: DirectoryId, name: &str) -> Option<usize> {
+        let size = self.directories[parent].files.remove(name)?;
+        self.invalidate_size_of(parent);
+        Some(size)
+    }
+
+    fn is_inside(&self, dir: DirectoryId, ancestor: DirectoryId) -> bool {
+        let mut current = Some(dir);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.directories[id].parent;
+        }
+        false
+    }
+
+    fn invalidate_size_of(&self, dir: DirectoryId) {
+        let mut current = Some(dir);
+        while let Some(id) = current {
+            self.directories[id].cached_size.set(None);
+            current = self.directories[id].parent;
+        }
+    }
+
+    fn size_of(&self, dir: DirectoryId) -> usize {
+        let directory = &self.directories[dir];
+        if let Some(size) = directory.cached_size.get() {
+            return size;
+        }
+        let size = directory.files.values().sum::<usize>()
+            + directory
+                .subdirectories
+                .values()
+                .map(|&id| self.size_of(id))
+                .sum::<usize>();
+        directory.cached_size.set(Some(size));
+        size
+    }
+
+    // resolves a path relative to `from`, or an absolute one if it starts with `/`
+    fn resolve(&self, from: DirectoryId, path: &str) -> Result<DirectoryId, String> {
+        let start = if path.starts_with('/') { ROOT } else { from };
+        path.split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .try_fold(start, |current, segment| {
+                if segment == ".." {
+                    self.directories[current]
+                        .parent
+                        .ok_or("cannot go above the root directory".to_string())
+                } else {
+                    self.directories[current]
+                        .subdirectories
+                        .get(segment)
+                        .copied()
+                        .ok_or(format!(
+                            "there is no directory '{segment}' in {}",
+                            self.path_of(current)
+                        ))
+                }
+            })
+    }
+
+    fn path_of(&self, dir: DirectoryId) -> String {
+        let mut segments = Vec::new();
+        let mut current = dir;
+        while let Some(parent) = self.directories[current].parent {
+            segments.push(self.directories[current].name.as_str());
+            current = parent;
+        }
+        segments.reverse();
+        format!("/{}", segments.join("/"))
+    }
+
+    // iterates over all directories depth-first, starting with the root
+    fn iter_directories(&self) -> impl Iterator<Item = DirectoryId> + '_ {
+        let mut stack = vec![ROOT];
+        std::iter::from_fn(move || {
+            let id = stack.pop()?;
+            stack.extend(self.directories[id].subdirectories.values().rev());
+            Some(id)
+        })
+    }
+
+    // iterates over all files as `(path, size)` pairs
+    fn iter_files(&self) -> impl Iterator<Item = (String, usize)> + '_ {
+        self.iter_directories().flat_map(move |id| {
+            let dir_path = self.path_of(id);
+            self.directories[id].files.iter().map(move |(name, &size)| {
+                let separator = if dir_path.ends_with('/') { "" } else { "/" };
+                (format!("{dir_path}{separator}{name}"), size)
+            })
+        })
+    }
+
+    fn depth_of(&self, dir: DirectoryId) -> usize {
+        let mut depth = 0;
+        let mut current = dir;
+        while let Some(parent) = self.directories[current].parent {
+            depth += 1;
+            current = parent;
+        }
+        depth
+    }
+
+    /*
+     * renders the contents of `dir` in the same style as the puzzle description, e.g.
+     * - / (dir, size=48381165)
+     *   - a (dir, size=94853)
+     *     - f (file, size=29116)
+     * directories deeper than `max_depth` below `dir` are shown without their contents
+     */
+    fn render_tree(&self, dir: DirectoryId, max_depth: Option<usize>) -> String {
+        let mut output = String::new();
+        self.render_tree_into(&mut output, dir, 0, max_depth);
+        output
+    }
+
+    fn render_tree_into(
+        &self,
+        output: &mut String,
+        dir: DirectoryId,
+        depth: usize,
+        max_depth: Option<usize>,
+    ) {
+        let directory = &self.directories[dir];
+        let indent = "  ".repeat(depth);
+        output.push_str(&format!(
+            "{indent}- {} (dir, size={})\n",
+            directory.name,
+            self.size_of(dir)
+        ));
+        if matches!(max_depth, Some(max_depth) if depth >= max_depth) {
+            return;
+        }
+        for &id in directory.subdirectories.values() {
+            self.render_tree_into(output, id, depth + 1, max_depth);
+        }
+        for (name, size) in &directory.files {
+            output.push_str(&format!("{indent}  - {name} (file, size={size})\n"));
+        }
+    }
+
+    // lists directories like `du` does, largest first, skipping those deeper than `max_depth`
+    fn render_du(&self, max_depth: Option<usize>) -> String {
+        self.iter_directories()
+            .filter(|&id| !matches!(max_depth, Some(max_depth) if self.depth_of(id) > max_depth))
+            .map(|id| (self.size_of(id), self.path_of(id)))
+            .sorted_by(|(lhs_size, lhs_path), (rhs_size, rhs_path)| {
+                rhs_size.cmp(lhs_size).then_with(|| lhs_path.cmp(rhs_path))
+            })
+            .map(|(size, path)| format!("{size}\t{path}\n"))
+            .collect()
+    }
+
+    fn to_json(&self, dir: DirectoryId) -> String {
+        let directory = &self.directories[dir];
+        let files = directory
+            .files
+            .iter()
+            .map(|(name, size)| format!("{{\"name\":{},\"size\":{size}}}", to_json_string(name)))
+            .join(",");
+        let subdirectories = directory
+            .subdirectories
+            .values()
+            .map(|&id| self.to_json(id))
+            .join(",");
+        format!(
+            "{{\"name\":{},\"size\":{},\"files\":[{files}],\"directories\":[{subdirectories}]}}",
+            to_json_string(&directory.name),
+            self.size_of(dir)
+        )
+    }
+}
+
+fn to_json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// splits a path into the directory containing the last segment and the last segment itself
+fn split_path(path: &str) -> (&str, &str) {
+    match path.trim_end_matches('/').rsplit_once('/') {
+        Some(("", name)) => ("/", name),
+        Some((parent, name)) => (parent, name),
+        None => (".", path),
+    }
+}
+
+/*
+ * replays the transcript, adding every directory and file that shows up in a listing;
+ * entries are keyed by their names, so listing the same directory again does not add anything twice;
+ * inconsistent lines are replayed as well as possible and reported as issues
+ */
+struct TranscriptReplay {
+    fs: FileSystem,
+    current: DirectoryId,
+    is_listing: bool,
+    issues: Vec<String>,
+    cat_outputs: Vec<(String, usize)>,
+}
+
+impl TranscriptReplay {
+    fn run(lines: &[TerminalLine]) -> Self {
+        let mut replay = Self {
+            fs: FileSystem::new(),
+            current: ROOT,
+            is_listing: false,
+            issues: Vec::new(),
+            cat_outputs: Vec::new(),
+        };
+        for (i, line) in lines.iter().enumerate() {
+            if let Err(issue) = replay.apply(line) {
+                replay.issues.push(format!("line {}: {issue}", i + 1));
+            }
+        }
+        replay
+    }
+
+    fn apply(&mut self, line: &TerminalLine) -> Result<(), String> {
         match line {
-            TerminalLine::Command(cmd) => match cmd {
-                Command::Cd { path } => {
-                    if path == "/" {
-                        if current_path.is_empty() {
-                            directories_with_size.insert("/".to_string(), 0);
-                            current_path.push("/".to_string());
-                        } else {
-                            current_path.drain(1..);
-                        }
-                    } else if path == ".." {
-                        current_path.pop();
-                    } else {
-                        current_path.push(path.to_string() + "/");
-                    }
+            TerminalLine::Command(cmd) => {
+                self.is_listing = matches!(cmd, Command::Ls);
+                match cmd {
+                    Command::Cd { path } => self.cd(path),
+                    Command::Ls => Ok(()),
+                    Command::Mkdir { path } => self.mkdir(path),
+                    Command::Rm { path } => self.rm(path),
+                    Command::Cat { path } => self.cat(path),
                 }
-                Command::Ls => {}
-            },
-            TerminalLine::Output(output) => match output {
-                Output::Dir { name } => {
-                    let absolute_path = current_path.join("") + name + "/";
-                    directories_with_size.insert(absolute_path, 0);
+            }
+            TerminalLine::Output(output) => {
+                if !self.is_listing {
+                    return Err("output does not belong to a listing".to_string());
                 }
-                Output::File { size } => {
-                    let mut absolute_path = String::new();
-                    for dir in &current_path {
-                        absolute_path += &dir;
-                        if let Some(old_size) = directories_with_size.get_mut(&absolute_path) {
-                            *old_size += size;
-                        }
+                self.add_listed_entry(output)
+            }
+        }
+    }
+
+    // changes directory even if it was never listed, creating it, so that the rest can still be replayed
+    fn cd(&mut self, path: &str) -> Result<(), String> {
+        let mut issue = None;
+        if path.starts_with('/') {
+            self.current = ROOT;
+        }
+        for segment in path.split('/').filter(|s| !s.is_empty() && *s != ".") {
+            if segment == ".." {
+                match self.fs.directories[self.current].parent {
+                    Some(parent) => self.current = parent,
+                    None => {
+                        issue.get_or_insert("cannot go above the root directory".to_string());
                     }
                 }
-            },
+            } else {
+                if !self.fs.directories[self.current]
+                    .subdirectories
+                    .contains_key(segment)
+                {
+                    issue.get_or_insert(format!(
+                        "changed into directory '{segment}' which was not listed in {}",
+                        self.fs.path_of(self.current)
+                    ));
+                }
+                self.current = self.fs.add_directory(self.current, segment);
+            }
+        }
+        match issue {
+            Some(issue) => Err(issue),
+            None => Ok(()),
+        }
+    }
+
+    // creates the directory along with any missing parents, like `mkdir -p`
+    fn mkdir(&mut self, path: &str) -> Result<(), String> {
+        if self.fs.resolve(self.current, path).is_ok() {
+            return Err(format!("directory '{path}' already exists"));
+        }
+        let mut current = if path.starts_with('/') {
+            ROOT
+        } else {
+            self.current
+        };
+        for segment in path.split('/').filter(|s| !s.is_empty() && *s != ".") {
+            current = if segment == ".." {
+                self.fs.directories[current]
+                    .parent
+                    .ok_or("cannot go above the root directory".to_string())?
+            } else {
+                self.fs.add_directory(current, segment)
+            };
+        }
+        Ok(())
+    }
+
+    fn rm(&mut self, path: &str) -> Result<(), String> {
+        let (parent_path, name) = split_path(path);
+        let parent = self.fs.resolve(self.current, parent_path)?;
+        if self.fs.remove_file(parent, name).is_some() {
+            return Ok(());
+        }
+        let dir = self.fs.resolve(parent, name)?;
+        if dir == ROOT {
+            return Err("cannot remove the root directory".to_string());
+        }
+        let is_current_removed = self.fs.is_inside(self.current, dir);
+        if is_current_removed {
+            self.current = self.fs.directories[dir].parent.unwrap();
+        }
+        self.fs.remove_directory(dir);
+        if is_current_removed {
+            Err(format!(
+                "removed '{path}' which contained the current directory"
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    // reports the size of the file, as that is all that is known about its contents
+    fn cat(&mut self, path: &str) -> Result<(), String> {
+        let (parent_path, name) = split_path(path);
+        let parent = self.fs.resolve(self.current, parent_path)?;
+        let size = *self.fs.directories[parent].files.get(name).ok_or(format!(
+            "there is no file '{name}' in {}",
+            self.fs.path_of(parent)
+        ))?;
+        self.cat_outputs.push((path.to_string(), size));
+        Ok(())
+    }
+
+    fn add_listed_entry(&mut self, output: &Output) -> Result<(), String> {
+        let directory = &self.fs.directories[self.current];
+        match output {
+            Output::Dir { name } => {
+                if directory.files.contains_key(name) {
+                    return Err(format!(
+                        "'{name}' is listed as a directory but it is a file"
+                    ));
+                }
+                self.fs.add_directory(self.current, name);
+                Ok(())
+            }
+            Output::File { name, size } => {
+                if directory.subdirectories.contains_key(name) {
+                    return Err(format!(
+                        "'{name}' is listed as a file but it is a directory"
+                    ));
+                }
+                let previous_size = directory.files.get(name).copied();
+                self.fs.add_file(self.current, name, *size);
+                match previous_size {
+                    Some(previous_size) if previous_size != *size => Err(format!(
+                        "file '{name}' was listed with a size of {previous_size} before, now it is {size}"
+                    )),
+                    _ => Ok(()),
+                }
+            }
         }
     }
-    directories_with_size
 }
 
-fn get_sum_of_directory_sizes_smaller_than(
-    n: usize,
-    directories: &HashMap<String, usize>,
-) -> usize {
-    directories.values().filter(|&s| s <= &n).sum()
+fn get_sum_of_directory_sizes_smaller_than(n: usize, fs: &FileSystem) -> usize {
+    fs.iter_directories()
+        .map(|id| fs.size_of(id))
+        .filter(|&s| s <= n)
+        .sum()
 }
 
-fn find_size_of_directory_to_delete(directories: &HashMap<String, usize>) -> usize {
-    let total_used_space = directories.get("/").unwrap();
+fn find_size_of_directory_to_delete(fs: &FileSystem) -> usize {
+    let total_used_space = fs.size_of(ROOT);
     let unused_space = TOTAL_DISK_SPACE - total_used_space;
     let additional_required_space = REQUIRED_FREE_SPACE - unused_space;
-    *directories
-        .values()
-        .filter(|&s| s >= &additional_required_space)
+    fs.iter_directories()
+        .map(|id| fs.size_of(id))
+        .filter(|&s| s >= additional_required_space)
         .min()
         .unwrap()
 }
 
+struct DiskSpace {
+    total: usize,
+    required_free: usize,
+}
+
+#[derive(Debug, Clone)]
+struct DeletionCandidate {
+    path: String,
+    size: usize,
+    is_directory: bool,
+    // index after the last candidate inside this one when listed depth-first
+    subtree_end: usize,
+}
+
+fn collect_deletion_candidates(
+    fs: &FileSystem,
+    dir: DirectoryId,
+    include_files: bool,
+    candidates: &mut Vec<DeletionCandidate>,
+) {
+    let idx = candidates.len();
+    let path = fs.path_of(dir);
+    candidates.push(DeletionCandidate {
+        path: path.clone(),
+        size: fs.size_of(dir),
+        is_directory: true,
+        subtree_end: 0,
+    });
+    for &id in fs.directories[dir].subdirectories.values() {
+        collect_deletion_candidates(fs, id, include_files, candidates);
+    }
+    if include_files {
+        let separator = if path.ends_with('/') { "" } else { "/" };
+        for (name, &size) in &fs.directories[dir].files {
+            let subtree_end = candidates.len() + 1;
+            candidates.push(DeletionCandidate {
+                path: format!("{path}{separator}{name}"),
+                size,
+                is_directory: false,
+                subtree_end,
+            });
+        }
+    }
+    candidates[idx].subtree_end = candidates.len();
+}
+
+#[derive(Clone)]
+struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    // sets `i + shift` for every `i` in `other`, ignoring the ones that do not fit
+    fn union_with_shifted(&mut self, other: &BitSet, shift: usize) {
+        let word_shift = shift / 64;
+        let bit_shift = shift % 64;
+        for (src, &word) in other.words.iter().enumerate() {
+            let dst = src + word_shift;
+            if word == 0 || dst >= self.words.len() {
+                continue;
+            }
+            self.words[dst] |= word << bit_shift;
+            if bit_shift > 0 && dst + 1 < self.words.len() {
+                self.words[dst + 1] |= word >> (64 - bit_shift);
+            }
+        }
+        let used_bits_of_last_word = self.len % 64;
+        if used_bits_of_last_word > 0 {
+            *self.words.last_mut().unwrap() &= (1 << used_bits_of_last_word) - 1;
+        }
+    }
+
+    // iterates over the elements that are in `self` but not in `other`
+    fn difference<'a>(&'a self, other: &'a BitSet) -> impl Iterator<Item = usize> + 'a {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .enumerate()
+            .flat_map(|(idx, (lhs, rhs))| {
+                let mut word = lhs & !rhs;
+                std::iter::from_fn(move || {
+                    if word == 0 {
+                        return None;
+                    }
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    Some(idx * 64 + bit)
+                })
+            })
+    }
+}
+
+#[derive(Debug)]
+struct DeletionPlan {
+    used_space: usize,
+    additional_required_space: usize,
+    deleted: Vec<DeletionCandidate>,
+}
+
+impl DeletionPlan {
+    fn freed_space(&self) -> usize {
+        self.deleted.iter().map(|c| c.size).sum()
+    }
+
+    fn explain(&self) -> String {
+        let mut explanation = format!(
+            "{} is used, so {} more needs to be freed\n",
+            self.used_space, self.additional_required_space
+        );
+        for candidate in &self.deleted {
+            let kind = if candidate.is_directory {
+                "directory"
+            } else {
+                "file"
+            };
+            explanation.push_str(&format!(
+                "delete {kind} {} freeing {}\n",
+                candidate.path, candidate.size
+            ));
+        }
+        explanation.push_str(&format!(
+            "which frees {} in total, {} more than needed",
+            self.freed_space(),
+            self.freed_space() - self.additional_required_space
+        ));
+        explanation
+    }
+}
+
+const UNREACHABLE: u32 = u32::MAX;
+// the tables of achievable totals take a few bytes per possible total, which is limited to this
+const MAX_NUM_OF_TOTALS: usize = 50_000_000;
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/*
+ * finds the set of non-nested directories (and files if `include_files` is set) with the smallest
+ * total size that frees enough space; candidates are listed depth-first, so choosing one means
+ * skipping everything up to its `subtree_end`, making `sums[i]`, the totals achievable using
+ * candidates from `i` onwards, equal to `sums[i + 1] ∪ (sums[subtree_end] + size)`;
+ * as `sums[i]` only grows with decreasing `i`, storing the largest `i` that can achieve each
+ * total is enough to reconstruct the choices, while only the sets at the ends of the subtrees
+ * currently being processed need to be kept around;
+ * the tables have an entry for every total up to the size of the smallest candidate that is
+ * enough on its own, so sizes are counted in units of their greatest common divisor, and planning
+ * fails if there would still be more than `MAX_NUM_OF_TOTALS` entries
+ */
+fn plan_deletion(
+    fs: &FileSystem,
+    disk: &DiskSpace,
+    include_files: bool,
+) -> Result<DeletionPlan, String> {
+    let used_space = fs.size_of(ROOT);
+    if used_space > disk.total {
+        return Err(format!(
+            "{used_space} is used, which does not fit on a disk of {}",
+            disk.total
+        ));
+    }
+    if disk.required_free > disk.total {
+        return Err(format!(
+            "{} cannot be freed on a disk of {}",
+            disk.required_free, disk.total
+        ));
+    }
+    let additional_required_space = (disk.required_free + used_space).saturating_sub(disk.total);
+
+    let mut candidates = Vec::new();
+    collect_deletion_candidates(fs, ROOT, include_files, &mut candidates);
+
+    // no combination is worth more than the smallest single candidate that frees enough space
+    let upper_bound = candidates
+        .iter()
+        .map(|c| c.size)
+        .filter(|&size| size >= additional_required_space)
+        .min()
+        .unwrap();
+
+    let unit = candidates.iter().map(|c| c.size).fold(0, gcd).max(1);
+    let upper_bound = upper_bound / unit;
+    let additional_required_units = additional_required_space.div_ceil(unit);
+    if upper_bound >= MAX_NUM_OF_TOTALS {
+        return Err(format!(
+            "Planning would need to consider {upper_bound} possible totals of {unit} bytes each, but at most {MAX_NUM_OF_TOTALS} are supported"
+        ));
+    }
+
+    let n = candidates.len();
+    let mut remaining_uses = HashMap::<usize, usize>::new();
+    for c in &candidates {
+        *remaining_uses.entry(c.subtree_end).or_default() += 1;
+    }
+
+    let mut reachable_from = vec![UNREACHABLE; upper_bound + 1];
+    reachable_from[0] = n as u32;
+    let mut sums = BitSet::new(upper_bound + 1);
+    sums.insert(0);
+    let mut saved_sums = HashMap::<usize, BitSet>::new();
+    if remaining_uses.contains_key(&n) {
+        saved_sums.insert(n, sums.clone());
+    }
+
+    for (i, candidate) in candidates.iter().enumerate().rev() {
+        let mut next_sums = sums.clone();
+        let end = candidate.subtree_end;
+        next_sums.union_with_shifted(saved_sums.get(&end).unwrap(), candidate.size / unit);
+        for sum in next_sums.difference(&sums) {
+            reachable_from[sum] = i as u32;
+        }
+        sums = next_sums;
+
+        let uses = remaining_uses.get_mut(&end).unwrap();
+        *uses -= 1;
+        if *uses == 0 {
+            saved_sums.remove(&end);
+        }
+        if remaining_uses.contains_key(&i) {
+            saved_sums.insert(i, sums.clone());
+        }
+    }
+
+    let mut remaining = (additional_required_units..=upper_bound)
+        .find(|&sum| reachable_from[sum] != UNREACHABLE)
+        .unwrap();
+    let mut deleted = Vec::new();
+    while remaining > 0 {
+        let candidate = &candidates[reachable_from[remaining] as usize];
+        remaining -= candidate.size / unit;
+        deleted.push(candidate.clone());
+    }
+
+    Ok(DeletionPlan {
+        used_space,
+        additional_required_space,
+        deleted,
+    })
+}
+
 fn main() {
     let input = read_file_lines_as("input/day7.txt", |l| TerminalLine::from_str(l).unwrap());
-    let directories_with_size = calculate_directory_sizes(&input);
+    let TranscriptReplay {
+        fs,
+        issues,
+        cat_outputs,
+        ..
+    } = TranscriptReplay::run(&input);
+    println!("The transcript has {} inconsistencies", issues.len());
+    for issue in &issues {
+        println!("{issue}");
+    }
+    for (path, size) in &cat_outputs {
+        println!("cat {path}: {size} bytes");
+    }
 
     let sum_of_directory_sizes_smaller_than_100000 =
-        get_sum_of_directory_sizes_smaller_than(100000, &directories_with_size);
+        get_sum_of_directory_sizes_smaller_than(100000, &fs);
     println!("The sum of directoriy sizes smaller than 100000 is {sum_of_directory_sizes_smaller_than_100000}");
 
-    let size_to_delete = find_size_of_directory_to_delete(&directories_with_size);
+    let size_to_delete = find_size_of_directory_to_delete(&fs);
     println!("The directory that should be deleted has a size of {size_to_delete}");
+
+    let largest_file = fs.iter_files().max_by_key(|(_, size)| *size);
+    if let Some((path, size)) = largest_file {
+        println!("The largest file is {path} with a size of {size}");
+    }
+    let deepest_directory = fs
+        .iter_directories()
+        .max_by_key(|&id| fs.depth_of(id))
+        .unwrap();
+    println!(
+        "The deepest directory is {} with a size of {}",
+        fs.path_of(deepest_directory),
+        fs.size_of(deepest_directory)
+    );
+
+    println!("The top 2 levels of the filesystem are:");
+    print!("{}", fs.render_tree(ROOT, Some(1)));
+
+    println!("The 5 largest directories are:");
+    for line in fs.render_du(None).lines().take(5) {
+        println!("{line}");
+    }
+
+    let deepest_directory_parent = fs.directories[deepest_directory].parent.unwrap();
+    println!(
+        "The parent of the deepest directory as JSON is {}",
+        fs.to_json(deepest_directory_parent)
+    );
+
+    let disk = DiskSpace {
+        total: TOTAL_DISK_SPACE,
+        required_free: REQUIRED_FREE_SPACE,
+    };
+    let directory_plan = plan_deletion(&fs, &disk, false).unwrap();
+    println!("The best plan deleting only directories is:");
+    println!("{}", directory_plan.explain());
+    let file_plan = plan_deletion(&fs, &disk, true).unwrap();
+    println!("The best plan deleting directories or files is:");
+    println!("{}", file_plan.explain());
 }
@@ -1,6 +1,42 @@
-use advent_of_code_2022::{parse_2d_number_grid, read_file_to_string};
+use advent_of_code_2022::{
+    heatmap_colour, parse_2d_number_grid, read_file_to_string, render_2d_array_as,
+    write_2d_array_as_ppm,
+};
 use array2d::Array2D;
-use take_until::TakeUntilExt;
+use itertools::Itertools;
+use std::fs::write;
+
+type Coords = (usize, usize);
+
+// the direction in which a tree looks out of the forest
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+struct ForestAnalysis {
+    visibility: Array2D<bool>,
+    // number of trees seen from each tree in each direction, indexed the same as `DIRECTIONS`
+    viewing_distances: [Array2D<u64>; 4],
+    scenic_scores: Array2D<u64>,
+}
+
+#[derive(Debug)]
+struct ScenicSpot {
+    coords: Coords,
+    scenic_score: u64,
+    viewing_distances: [u64; 4],
+}
 
 struct Forest {
     height_map: Array2D<u8>,
@@ -12,87 +48,196 @@ impl Forest {
         Self { height_map }
     }
 
-    fn is_tree_visible(&self, y: usize, x: usize) -> bool {
-        let height = self.height_map.get(y, x).unwrap();
-        let is_hidden_from_top = (0..y)
-            .map(|i| self.height_map.get(i, x).unwrap())
-            .any(|tree| tree >= height);
-        if !is_hidden_from_top {
-            return true;
-        }
-        let is_hidden_from_bottom = ((y + 1)..self.height_map.num_rows())
-            .map(|i| self.height_map.get(i, x).unwrap())
-            .any(|tree| tree >= height);
-        if !is_hidden_from_bottom {
-            return true;
-        }
-        let is_hidden_from_left = (0..x)
-            .map(|i| self.height_map.get(y, i).unwrap())
-            .any(|tree| tree >= height);
-        if !is_hidden_from_left {
-            return true;
+    /*
+     * returns every line of trees in the forest, each ordered starting from the edge in `dir`,
+     * e.g. row `0` for `Up`, so that looking in `dir` from any tree means looking back along its line
+     */
+    fn get_lines_looking_towards(&self, dir: Direction) -> Vec<Vec<Coords>> {
+        let num_rows = self.height_map.num_rows();
+        let num_columns = self.height_map.num_columns();
+        match dir {
+            Direction::Up => (0..num_columns)
+                .map(|x| (0..num_rows).map(|y| (y, x)).collect())
+                .collect(),
+            Direction::Down => (0..num_columns)
+                .map(|x| (0..num_rows).rev().map(|y| (y, x)).collect())
+                .collect(),
+            Direction::Left => (0..num_rows)
+                .map(|y| (0..num_columns).map(|x| (y, x)).collect())
+                .collect(),
+            Direction::Right => (0..num_rows)
+                .map(|y| (0..num_columns).rev().map(|x| (y, x)).collect())
+                .collect(),
         }
+    }
+
+    /*
+     * sweeps each line keeping a stack of the trees that can still block the view of later ones,
+     * which are the ones not shorter than any tree after them, so their heights decrease from the
+     * bottom to the top; a new tree pops the shorter ones, leaving the first blocking tree on top,
+     * or nothing if the tree can see all the way to the edge, meaning it is visible from there
+     */
+    fn calculate_viewing_distances_towards(&self, dir: Direction) -> (Array2D<bool>, Array2D<u64>) {
+        let num_rows = self.height_map.num_rows();
+        let num_columns = self.height_map.num_columns();
+        let mut visibility = Array2D::filled_with(false, num_rows, num_columns);
+        let mut viewing_distances = Array2D::filled_with(0, num_rows, num_columns);
+
+        let mut stack = Vec::<(usize, u8)>::new();
+        for line in self.get_lines_looking_towards(dir) {
+            stack.clear();
+            for (i, &(y, x)) in line.iter().enumerate() {
+                let height = *self.height_map.get(y, x).unwrap();
+                while let Some(&(_, blocking_height)) = stack.last() {
+                    if blocking_height >= height {
+                        break;
+                    }
+                    stack.pop();
+                }
+
+                let viewing_distance = match stack.last() {
+                    Some(&(j, _)) => i - j,
+                    None => {
+                        visibility.set(y, x, true).unwrap();
+                        i
+                    }
+                };
+                viewing_distances
+                    .set(y, x, viewing_distance as u64)
+                    .unwrap();
 
-        let is_hidden_from_right = ((x + 1)..self.height_map.num_columns())
-            .map(|i| self.height_map.get(y, i).unwrap())
-            .any(|tree| tree >= height);
-        if !is_hidden_from_right {
-            return true;
+                stack.push((i, height));
+            }
         }
 
-        false
+        (visibility, viewing_distances)
+    }
+
+    fn analyse(&self) -> ForestAnalysis {
+        let num_rows = self.height_map.num_rows();
+        let num_columns = self.height_map.num_columns();
+        let mut visibility = Array2D::filled_with(false, num_rows, num_columns);
+        let mut scenic_scores = Array2D::filled_with(1, num_rows, num_columns);
+
+        let viewing_distances = DIRECTIONS.map(|dir| {
+            let (visible_from_edge, distances) = self.calculate_viewing_distances_towards(dir);
+            for (y, x) in self.height_map.indices_row_major() {
+                if *visible_from_edge.get(y, x).unwrap() {
+                    visibility.set(y, x, true).unwrap();
+                }
+                *scenic_scores.get_mut(y, x).unwrap() *= distances.get(y, x).unwrap();
+            }
+            distances
+        });
+
+        ForestAnalysis {
+            visibility,
+            viewing_distances,
+            scenic_scores,
+        }
     }
+}
 
+impl ForestAnalysis {
     fn calculate_number_of_visible_trees(&self) -> u32 {
-        self.height_map
-            .indices_row_major()
-            .map(|(y, x)| self.is_tree_visible(y, x))
-            .filter(|a| *a)
+        self.visibility
+            .elements_row_major_iter()
+            .filter(|&&visible| visible)
             .count() as u32
     }
 
-    fn calculate_scenic_score_for(&self, y: usize, x: usize) -> u32 {
-        let height = self.height_map.get(y, x).unwrap();
-        let top_score = (0..y)
-            .rev()
-            .map(|i| self.height_map.get(i, x).unwrap())
-            .take_until(|&tree| tree >= height)
-            .count();
-
-        let bottom_score = ((y + 1)..self.height_map.num_rows())
-            .map(|i| self.height_map.get(i, x).unwrap())
-            .take_until(|&tree| tree >= height)
-            .count();
-
-        let left_score = (0..x)
-            .rev()
-            .map(|i| self.height_map.get(y, i).unwrap())
-            .take_until(|&tree| tree >= height)
-            .count();
-
-        let right_score = ((x + 1)..self.height_map.num_columns())
-            .map(|i| self.height_map.get(y, i).unwrap())
-            .take_until(|&tree| tree >= height)
-            .count();
-
-        (top_score * bottom_score * left_score * right_score) as u32
+    fn calculate_highest_scenic_score(&self) -> u64 {
+        *self.scenic_scores.elements_row_major_iter().max().unwrap()
     }
 
-    fn calculate_highest_scenic_score(&self) -> u32 {
-        self.height_map
+    fn find_top_scenic_spots(&self, k: usize) -> Vec<ScenicSpot> {
+        self.scenic_scores
             .indices_row_major()
-            .map(|(y, x)| self.calculate_scenic_score_for(y, x))
-            .max()
-            .unwrap()
+            .map(|(y, x)| ScenicSpot {
+                coords: (y, x),
+                scenic_score: *self.scenic_scores.get(y, x).unwrap(),
+                viewing_distances: DIRECTIONS
+                    .map(|dir| *self.viewing_distances[dir as usize].get(y, x).unwrap()),
+            })
+            .sorted_by(|lhs, rhs| rhs.scenic_score.cmp(&lhs.scenic_score))
+            .take(k)
+            .collect()
+    }
+
+    // scales scenic scores logarithmically, as only a few trees have scores close to the highest one
+    fn get_scenic_score_intensity(scenic_score: u64, highest_scenic_score: u64) -> f64 {
+        if highest_scenic_score == 0 {
+            return 0.0;
+        }
+        (scenic_score as f64).ln_1p() / (highest_scenic_score as f64).ln_1p()
+    }
+
+    fn render_visibility_mask(&self) -> String {
+        render_2d_array_as(&self.visibility, |&visible| {
+            if visible { "#" } else { "." }.to_string()
+        })
+    }
+
+    fn render_viewing_distances(&self, dir: Direction) -> String {
+        render_2d_array_as(&self.viewing_distances[dir as usize], |distance| {
+            format!("{distance:3}")
+        })
+    }
+
+    fn render_scenic_score_heatmap(&self) -> String {
+        const SHADES: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+        let highest_scenic_score = self.calculate_highest_scenic_score();
+        render_2d_array_as(&self.scenic_scores, |&scenic_score| {
+            let intensity = Self::get_scenic_score_intensity(scenic_score, highest_scenic_score);
+            let shade = (intensity * (SHADES.len() - 1) as f64).round() as usize;
+            SHADES[shade].to_string()
+        })
+    }
+
+    fn write_scenic_score_heatmap_image(&self, path: &str) -> std::io::Result<()> {
+        let highest_scenic_score = self.calculate_highest_scenic_score();
+        write_2d_array_as_ppm(&self.scenic_scores, path, |&scenic_score| {
+            heatmap_colour(Self::get_scenic_score_intensity(
+                scenic_score,
+                highest_scenic_score,
+            ))
+        })
     }
 }
 
 fn main() {
     let input = read_file_to_string("input/day8.txt");
     let forest = Forest::new(&input);
-    let number_of_visible_trees = forest.calculate_number_of_visible_trees();
+    let analysis = forest.analyse();
+    let number_of_visible_trees = analysis.calculate_number_of_visible_trees();
     println!("The number of visible trees in the forest is {number_of_visible_trees}");
 
-    let highest_scenic_score = forest.calculate_highest_scenic_score();
+    let highest_scenic_score = analysis.calculate_highest_scenic_score();
     println!("The highest scenic score in the forest is {highest_scenic_score}");
+
+    println!("The 5 most scenic spots are:");
+    for spot in analysis.find_top_scenic_spots(5) {
+        let [up, down, left, right] = spot.viewing_distances;
+        println!(
+            "{:?} with a score of {}, seeing {up} up, {down} down, {left} left and {right} right",
+            spot.coords, spot.scenic_score
+        );
+    }
+
+    let mut heatmaps = format!(
+        "Visibility:\n{}\nScenic scores:\n{}",
+        analysis.render_visibility_mask(),
+        analysis.render_scenic_score_heatmap()
+    );
+    for dir in DIRECTIONS {
+        heatmaps.push_str(&format!(
+            "\nViewing distances looking {dir:?}:\n{}",
+            analysis.render_viewing_distances(dir)
+        ));
+    }
+    write("day8_heatmaps.txt", heatmaps).unwrap();
+    analysis
+        .write_scenic_score_heatmap_image("day8_scenic_scores.ppm")
+        .unwrap();
+    println!("The heatmaps were written to day8_heatmaps.txt and day8_scenic_scores.ppm");
 }
@@ -1,6 +1,12 @@
 use advent_of_code_2022::read_file_lines_as;
+use itertools::Itertools;
 use sscanf::scanf;
-use std::{collections::HashSet, str::FromStr};
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+    fs::write,
+    str::FromStr,
+};
 
 type Coords = (i32, i32);
 
@@ -10,80 +16,129 @@ enum Direction {
     R,
     U,
     D,
+    UL,
+    UR,
+    DL,
+    DR,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "L" => Ok(Self::L),
+            "R" => Ok(Self::R),
+            "U" => Ok(Self::U),
+            "D" => Ok(Self::D),
+            "UL" => Ok(Self::UL),
+            "UR" => Ok(Self::UR),
+            "DL" => Ok(Self::DL),
+            "DR" => Ok(Self::DR),
+            _ => Err(format!("Invalid direction '{s}'")),
+        }
+    }
 }
 
 impl Direction {
-    fn from_char(c: char) -> Self {
-        match c {
-            'L' => Self::L,
-            'R' => Self::R,
-            'U' => Self::U,
-            'D' => Self::D,
-            _ => unreachable!(),
+    fn get_movement_vector(&self) -> Coords {
+        match self {
+            Self::L => (-1, 0),
+            Self::R => (1, 0),
+            Self::U => (0, 1),
+            Self::D => (0, -1),
+            Self::UL => (-1, 1),
+            Self::UR => (1, 1),
+            Self::DL => (-1, -1),
+            Self::DR => (1, -1),
         }
     }
 }
 
+/*
+ * a movement is either `<direction> <steps>`, e.g. `R 4` or `UL 2`,
+ * or `V <x> <y>`, moving the head by the given vector, e.g. `V 3 -2`,
+ * which is done by stepping diagonally until one of the coordinates is reached, then straight
+ */
 #[derive(Debug)]
-struct Movement {
-    dir: Direction,
-    n: u32,
+enum Movement {
+    Steps { dir: Direction, n: u32 },
+    Vector { x: i32, y: i32 },
 }
 
 impl FromStr for Movement {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (d, n): (char, u32) =
-            scanf!(s, "{char} {u32}").map_err(|_| "Invalid format".to_string())?;
-        let dir = Direction::from_char(d);
-        Ok(Self { dir, n })
+        if let Ok((x, y)) = scanf!(s, "V {i32} {i32}") {
+            return Ok(Self::Vector { x, y });
+        }
+        let (d, n) = s.split_once(' ').ok_or(format!("Invalid movement '{s}'"))?;
+        let dir = Direction::from_str(d)?;
+        let n = n
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid number of steps in movement '{s}'"))?;
+        Ok(Self::Steps { dir, n })
+    }
+}
+
+impl Display for Movement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Steps { dir, n } => write!(f, "{dir:?} {n}"),
+            Self::Vector { x, y } => write!(f, "V {x} {y}"),
+        }
     }
 }
 
-struct Rope<const N: usize> {
-    knot_positions: [Coords; N],
-    tail_visited_positions: HashSet<(i32, i32)>,
+struct Rope {
+    knot_positions: Vec<Coords>,
+    visited_positions: Vec<HashSet<Coords>>,
 }
 
-impl<const N: usize> Rope<N> {
-    fn new() -> Self {
+impl Rope {
+    fn new(num_of_knots: usize) -> Self {
+        assert!(num_of_knots > 0, "A rope must have at least 1 knot");
         Self {
-            knot_positions: [(0, 0); N],
-            tail_visited_positions: HashSet::new(),
+            knot_positions: vec![(0, 0); num_of_knots],
+            visited_positions: vec![HashSet::from([(0, 0)]); num_of_knots],
         }
     }
 
     fn simulate_movement(&mut self, movement: &Movement) {
-        for _ in 0..movement.n {
-            self.move_once(&movement.dir);
+        match movement {
+            Movement::Steps { dir, n } => {
+                let head_movement = dir.get_movement_vector();
+                for _ in 0..*n {
+                    self.move_once(head_movement);
+                }
+            }
+            Movement::Vector { x, y } => {
+                let (mut remaining_x, mut remaining_y) = (*x, *y);
+                while (remaining_x, remaining_y) != (0, 0) {
+                    let head_movement = (remaining_x.signum(), remaining_y.signum());
+                    self.move_once(head_movement);
+                    remaining_x -= head_movement.0;
+                    remaining_y -= head_movement.1;
+                }
+            }
         }
     }
 
-    fn move_once(&mut self, dir: &Direction) {
-        let head_movement = Self::get_head_movement_vector(dir);
+    fn move_once(&mut self, head_movement: Coords) {
         self.knot_positions[0].0 += head_movement.0;
         self.knot_positions[0].1 += head_movement.1;
 
-        for i in 1..N {
+        self.visited_positions[0].insert(self.knot_positions[0]);
+
+        for i in 1..self.knot_positions.len() {
             let tail_movement = Self::get_tail_movement_vector(
                 &self.knot_positions[i - 1],
                 &self.knot_positions[i],
             );
             self.knot_positions[i].0 += tail_movement.0;
             self.knot_positions[i].1 += tail_movement.1;
-        }
-
-        self.tail_visited_positions
-            .insert(self.knot_positions[N - 1]);
-    }
-
-    fn get_head_movement_vector(dir: &Direction) -> (i32, i32) {
-        match dir {
-            Direction::L => (-1, 0),
-            Direction::R => (1, 0),
-            Direction::U => (0, 1),
-            Direction::D => (0, -1),
+            self.visited_positions[i].insert(self.knot_positions[i]);
         }
     }
 
@@ -133,14 +188,117 @@ impl<const N: usize> Rope<N> {
         }
     }
 
+    fn get_number_of_visited_positions_by_knot(&self, knot: usize) -> u32 {
+        self.visited_positions[knot].len() as u32
+    }
+
     fn get_number_of_visited_positions_by_tail(&self) -> u32 {
-        self.tail_visited_positions.len() as u32
+        self.get_number_of_visited_positions_by_knot(self.knot_positions.len() - 1)
+    }
+
+    // returns the bottom left and top right corners of the area the knot has been in
+    fn get_bounding_box_of_knot(&self, knot: usize) -> (Coords, Coords) {
+        let visited = &self.visited_positions[knot];
+        let (min_x, max_x) = visited.iter().map(|p| p.0).minmax().into_option().unwrap();
+        let (min_y, max_y) = visited.iter().map(|p| p.1).minmax().into_option().unwrap();
+        ((min_x, min_y), (max_x, max_y))
     }
+
+    fn get_knot_label(&self, knot: usize) -> char {
+        if knot == 0 {
+            'H'
+        } else if self.knot_positions.len() == 2 {
+            'T'
+        } else {
+            char::from_digit(knot as u32, 36).unwrap_or('*')
+        }
+    }
+
+    // renders an area like the puzzle description does, with `y` growing upwards
+    fn render_area(
+        &self,
+        (min_x, min_y): Coords,
+        (max_x, max_y): Coords,
+        f: impl Fn(Coords) -> char,
+    ) -> String {
+        let mut output = String::new();
+        for y in (min_y..=max_y).rev() {
+            output.extend((min_x..=max_x).map(|x| f((x, y))));
+            output.push('\n');
+        }
+        output
+    }
+
+    // draws the knots with the start, where the knot earlier in the rope covers the later ones
+    fn render_knots(&self) -> String {
+        let (min_x, max_x) = self
+            .knot_positions
+            .iter()
+            .map(|p| p.0)
+            .chain([0])
+            .minmax()
+            .into_option()
+            .unwrap();
+        let (min_y, max_y) = self
+            .knot_positions
+            .iter()
+            .map(|p| p.1)
+            .chain([0])
+            .minmax()
+            .into_option()
+            .unwrap();
+        self.render_area(
+            (min_x - 1, min_y - 1),
+            (max_x + 1, max_y + 1),
+            |pos| match self.knot_positions.iter().position(|&knot| knot == pos) {
+                Some(knot) => self.get_knot_label(knot),
+                None if pos == (0, 0) => 's',
+                None => '.',
+            },
+        )
+    }
+
+    fn render_tail_trail(&self) -> String {
+        let tail = self.knot_positions.len() - 1;
+        let (bottom_left, top_right) = self.get_bounding_box_of_knot(tail);
+        self.render_area(bottom_left, top_right, |pos| {
+            if pos == (0, 0) {
+                's'
+            } else if self.visited_positions[tail].contains(&pos) {
+                '#'
+            } else {
+                '.'
+            }
+        })
+    }
+}
+
+/*
+ * each knot only follows the one before it, so the first `n` knots of a longer rope move exactly
+ * like a rope of `n` knots, meaning a single rope as long as the longest one is enough to
+ * simulate all lengths at once; returns the number of positions visited by the tail of each
+ */
+fn simulate_ropes_of_lengths(movements: &[Movement], lengths: &[usize]) -> Vec<u32> {
+    assert!(
+        lengths.iter().all(|&n| n > 0),
+        "A rope must have at least 1 knot"
+    );
+    let Some(longest) = lengths.iter().copied().max() else {
+        return Vec::new();
+    };
+    let mut rope = Rope::new(longest);
+    for m in movements {
+        rope.simulate_movement(m);
+    }
+    lengths
+        .iter()
+        .map(|&n| rope.get_number_of_visited_positions_by_knot(n - 1))
+        .collect()
 }
 
 fn main() {
     let movements = read_file_lines_as("input/day9.txt", |l| Movement::from_str(l).unwrap());
-    let mut short_rope = Rope::<2>::new();
+    let mut short_rope = Rope::new(2);
 
     for m in &movements {
         short_rope.simulate_movement(m);
@@ -149,11 +307,35 @@ fn main() {
         short_rope.get_number_of_visited_positions_by_tail();
     println!("The number of positions the tail of the short rope visited at least once is {number_of_positions_tail_visited_short_rope}");
 
-    let mut long_rope = Rope::<10>::new();
+    let mut long_rope = Rope::new(10);
     for m in &movements {
         long_rope.simulate_movement(m);
     }
     let number_of_positions_tail_visited_long_rope =
         long_rope.get_number_of_visited_positions_by_tail();
     println!("The number of positions the tail of the long rope visited at least once is {number_of_positions_tail_visited_long_rope}");
+
+    for knot in 0..10 {
+        let visited = long_rope.get_number_of_visited_positions_by_knot(knot);
+        let (bottom_left, top_right) = long_rope.get_bounding_box_of_knot(knot);
+        println!("Knot {knot} of the long rope visited {visited} positions between {bottom_left:?} and {top_right:?}");
+    }
+
+    let lengths = [2, 5, 10, 20];
+    let visited_positions_for_lengths = simulate_ropes_of_lengths(&movements, &lengths);
+    for (n, visited) in lengths.iter().zip(visited_positions_for_lengths) {
+        println!("The tail of a rope with {n} knots visited {visited} positions");
+    }
+
+    let mut rope = Rope::new(10);
+    println!("== Initial State ==");
+    println!("{}", rope.render_knots());
+    for m in movements.iter().take(3) {
+        rope.simulate_movement(m);
+        println!("== {m} ==");
+        println!("{}", rope.render_knots());
+    }
+
+    write("day9_tail_trail.txt", long_rope.render_tail_trail()).unwrap();
+    println!("The trail of the tail of the long rope was written to day9_tail_trail.txt");
 }
@@ -2,7 +2,8 @@ use array2d::Array2D;
 use std::{
     convert::{identity, TryInto},
     fmt::Display,
-    fs::read_to_string,
+    fs::{read_to_string, write},
+    io,
     path::Path,
 };
 
@@ -97,6 +98,40 @@ pub fn print_usize_2d_array_with_delim(array: &Array2D<usize>) {
     }
 }
 
+pub fn render_2d_array_as<T: Clone>(array: &Array2D<T>, f: impl Fn(&T) -> String) -> String {
+    let mut output = String::new();
+    for row in array.rows_iter() {
+        for column in row.into_iter() {
+            output.push_str(&f(column));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+// writes the array as a binary PPM image, with one pixel for each element
+pub fn write_2d_array_as_ppm<T: Clone>(
+    array: &Array2D<T>,
+    path: impl AsRef<Path>,
+    f: impl Fn(&T) -> [u8; 3],
+) -> io::Result<()> {
+    let header = format!("P6\n{} {}\n255\n", array.num_columns(), array.num_rows());
+    let mut contents = header.into_bytes();
+    for row in array.rows_iter() {
+        for column in row.into_iter() {
+            contents.extend(f(column));
+        }
+    }
+    write(path, contents)
+}
+
+// maps `0.0..=1.0` to a colour going from black through red and yellow to white
+pub fn heatmap_colour(intensity: f64) -> [u8; 3] {
+    let scaled = (intensity.clamp(0.0, 1.0) * 3.0 * 255.0) as u32;
+    let channel = |offset: u32| scaled.saturating_sub(offset * 255).min(255) as u8;
+    [channel(0), channel(1), channel(2)]
+}
+
 pub fn parse_2d_grid_as<T: Clone>(s: &str, f: fn(char) -> T) -> Array2D<T> {
     let elements = &*s
         .lines()